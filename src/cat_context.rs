@@ -6,21 +6,30 @@
 //! a tagů
 //!
 //! `CatContext` také může být vytvořen
-//! uměle použitím funkce `CatContext::new()`
+//! uměle pomocí [`CatContextBuilder`] z vlastních
+//! zdrojů karet a historie (viz modul [`crate::sources`]),
+//! nebo použitím funkce `CatContext::new()`
 //! a zaplněním struktury vlastními prvky.
 //!
 //! Varování: tento modul mutuje kniho
 //! (vymazává headery ze souborů).
 use walkdir::WalkDir;
-use mdbook::book::{Book, BookItem};
+use chrono::{DateTime, Local, NaiveDate};
+use mdbook::book::Book;
 
+use std::cmp::Reverse;
 use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
 use crate::error::CatError;
+use crate::config::{CatConfig, VocabularyMode};
 use crate::models::*;
+use crate::render;
+use crate::sources::{BookCards, CardSource, GitHistory, HistorySource};
+use crate::stats;
+use crate::tags;
 
 /// funkce, která vykrojí header daného stringu
 pub fn extract_header(src: &str) -> Result<(String, String), CatError> {
@@ -88,6 +97,125 @@ pub fn read_teacher_cards() -> Result<Vec<TeacherCard>, CatError> {
 	Ok(teachers.into_iter().map(|(_, x)| x.unwrap()).collect::<Vec<TeacherCard>>())
 }
 
+/// přečte karty tagů ze souboru `tags.toml`,
+/// klíčem je kanonický tvar tagu.
+///
+/// Soubor je nepovinný, pokud neexistuje,
+/// žádné karty tagů nejsou
+pub fn read_tag_cards() -> Result<HashMap<String, TagCard>, CatError> {
+	let path = Path::new("tags.toml");
+
+	if !path.is_file() {
+		return Ok(HashMap::new());
+	}
+
+	let content = read_to_string(path).map_err(|e| CatError::ReadFailed {
+		path:  path.display().to_string(),
+		error: e.to_string(),
+	})?;
+
+	toml::de::from_str(&content).map_err(|err| CatError::InvalidTagCards { err })
+}
+
+/// přečte slovník tagů z daného souboru,
+/// cesta je relativní ke kořeni knihy
+pub fn read_vocabulary(path: &str) -> Result<TagGroup, CatError> {
+	let content = read_to_string(path)
+		.map_err(|e| CatError::ReadFailed { path: path.into(), error: e.to_string() })?;
+
+	toml::de::from_str(&content)
+		.map_err(|err| CatError::InvalidVocabulary { path: path.into(), err })
+}
+
+/// pokusí se přečíst hodnotu `datum` jako datum,
+/// podporovány jsou formáty `20.1.2019`, `20. 1. 2019` a `2019-01-20`
+pub fn parse_datum(datum: &str) -> Option<NaiveDate> {
+	["%d.%m.%Y", "%d. %m. %Y", "%Y-%m-%d"]
+		.iter()
+		.find_map(|f| NaiveDate::parse_from_str(datum.trim(), f).ok())
+}
+
+/// seřadí články předmětu podle daného řazení.
+///
+/// `summary` obsahuje cesty článků v pořadí, v jakém jsou v knize
+pub fn sort_articles(articles: &mut Vec<Article>, razeni: Razeni, summary: &[PathBuf]) {
+	articles.sort_by(|a, b| a.card.nazev.cmp(&b.card.nazev));
+
+	match razeni {
+		Razeni::Nazev => (),
+		Razeni::Summary => articles.sort_by_key(|a| summary.iter().position(|x| *x == a.path)),
+		Razeni::Datum => articles.sort_by_key(|a| {
+			// články bez data až na konci
+			let date = a.card.datum.as_ref().and_then(|x| parse_datum(x));
+			(date.is_none(), date)
+		}),
+		// články bez historie až na konci
+		Razeni::LastModified => articles.sort_by_key(|a| Reverse(a.modified_timestamp)),
+		Razeni::Poradi => articles.sort_by_key(|a| (a.card.poradi.is_none(), a.card.poradi)),
+	}
+}
+
+/// zjistí, zda článek může být zastaralý
+///
+/// článek je zastaralý, pokud uplynulo datum `revidovat_do`,
+/// nebo pokud od poslední změny uběhlo více dní, než udává
+/// `revidovat_po`, popř. globální `max_stari`. Stáří článku
+/// bez historie (`modified_timestamp` je `None`) se neposuzuje
+pub fn is_stale(
+	card: &ArticleCard,
+	modified_timestamp: Option<i64>,
+	max_stari: Option<u64>,
+	now: DateTime<Local>,
+) -> bool {
+	if let Some(date) = card.revidovat_do.as_ref().and_then(|x| parse_datum(x)) {
+		if date < now.naive_local().date() {
+			return true;
+		}
+	}
+
+	match (card.revidovat_po.or(max_stari), modified_timestamp) {
+		(Some(days), Some(modified)) => {
+			now.timestamp() - modified > days as i64 * 24 * 60 * 60
+		}
+		_ => false,
+	}
+}
+
+/// sestaví vazby mezi vyučujícími a předměty
+///
+/// - zodpovědná osoba odpovídá hodnotě `zodpovedna_osoba` předmětu
+/// - autor založil `subject.md` nebo některý z materiálů předmětu
+/// - přispěvatel některý z těchto souborů upravil, ale žádný nezaložil
+pub fn subject_roles(subjects: &[Subject], teachers: &[Teacher]) -> Vec<SubjectRole> {
+	let mut roles = vec![];
+
+	for s in subjects {
+		let files = s.articles.iter().map(|a| &a.path).chain(Some(&s.path)).collect::<Vec<_>>();
+
+		for t in teachers {
+			let mut push = |role| {
+				roles.push(SubjectRole {
+					username: t.card.username.clone(),
+					subject: s.path.clone(),
+					role,
+				})
+			};
+
+			if t.card.matches(&s.card.zodpovedna_osoba) {
+				push(Role::Responsible);
+			}
+
+			if files.iter().any(|f| t.files_created.contains(f)) {
+				push(Role::Author);
+			} else if files.iter().any(|f| t.files_modified.contains(f)) {
+				push(Role::Contributor);
+			}
+		}
+	}
+
+	roles
+}
+
 /// Cat kontext
 ///
 /// Typ obsahující kompletní kotext `cat-prepu`.
@@ -110,6 +238,8 @@ pub struct CatContext {
 	pub article_cards: Vec<ArticleCard>,
 	/// články
 	pub articles:      Vec<Article>,
+	/// vazby mezi vyučujícími a předměty
+	pub subject_roles: Vec<SubjectRole>,
 	/// obsahuje hashmapu tagů
 	///
 	/// tagy jsou sesbírány z jednotlivých
@@ -122,7 +252,25 @@ pub struct CatContext {
 	///
 	/// `TagContext` je následně využit jako šablonový
 	/// kontext pro generování stránky s tagy.
+	///
+	/// tagy jsou sloučeny podle normalizovaného tvaru
+	/// (viz [`crate::tags::canonicalize_tags`]) a klíčem
+	/// je jejich kanonický tvar. Obsahuje i předky
+	/// hierarchických tagů, viz [`crate::tags::collect_tags`]
 	pub tags:          HashMap<String, Vec<ArticleCard>>,
+	/// unikátní slugy tagů podle názvu, viz [`crate::render::tag_slugs`]
+	pub tag_slugs:     HashMap<String, String>,
+	/// unikátní slugy vyučujících podle usernamu,
+	/// viz [`crate::render::teacher_slugs`]
+	pub teacher_slugs: HashMap<String, String>,
+	/// strom hierarchických tagů
+	pub tag_tree:      Vec<TagNode>,
+	/// karty tagů ze souboru `tags.toml`
+	pub tag_cards:     HashMap<String, TagCard>,
+	/// slovník tagů, pokud je nastaven
+	pub vocabulary:    Option<TagGroup>,
+	/// konfigurace, se kterou byl kontext vytvořen
+	pub config:        CatConfig,
 }
 
 impl CatContext {
@@ -136,97 +284,135 @@ impl CatContext {
 			teachers:      vec![],
 			subjects:      vec![],
 			articles:      vec![],
+			subject_roles: vec![],
 			tags:          HashMap::new(),
+			tag_slugs:     HashMap::new(),
+			teacher_slugs: HashMap::new(),
+			tag_tree:      vec![],
+			tag_cards:     HashMap::new(),
+			vocabulary:    None,
+			config:        CatConfig::default(),
 		}
 	}
 
 	/// vygeneruje kontext dle knihy.
 	/// Tato funkce knihu mutuje, protože odděluje headery
-	/// od obsahu jednotlivých souborů
-	pub fn with_book(src: &mut Book) -> Result<CatContext, CatError> {
-		let (status, is_inside, error) = sh!("git rev-parse --is-inside-work-tree");
+	/// od obsahu jednotlivých souborů.
+	///
+	/// Karty se čtou z knihy a historie z gitu,
+	/// viz [`CatContextBuilder`]
+	pub fn with_book(src: &mut Book, config: &CatConfig) -> Result<CatContext, CatError> {
+		let history = GitHistory::new(&config.src)?;
 
-		if status != 0 || !is_inside.trim().parse().unwrap_or(false) {
-			return Err(CatError::NotARepo { error });
-		}
+		CatContextBuilder::new(BookCards::new(src, config), history).config(config).build()
+	}
 
-		let mut teacher_cards = read_teacher_cards()?;
+	/// slug daného tagu, pro tagy mimo [`CatContext::tag_slugs`]
+	/// je použit [`crate::render::slugify`]
+	pub fn tag_slug(&self, tag: &str) -> String {
+		self.tag_slugs.get(tag).cloned().unwrap_or_else(|| render::slugify(tag))
+	}
+
+	/// slug vyučujícího s daným usernamem, pro usernamy mimo
+	/// [`CatContext::teacher_slugs`] je použit [`crate::render::slugify`]
+	pub fn teacher_slug(&self, username: &str) -> String {
+		self.teacher_slugs
+			.get(username)
+			.cloned()
+			.unwrap_or_else(|| render::slugify(username))
+	}
+
+	/// hlavní předmět článku, viz [`Article::subject_card`]
+	pub fn subject_of(&self, article: &Article) -> Option<&Subject> {
+		let card = article.subject_card.as_ref()?;
+
+		self.subjects.iter().find(|s| Some(&s.path) == card._resolved_path.as_ref())
+	}
+}
+
+/// Sestavovač [`CatContext`]
+///
+/// Karty čte z [`CardSource`], historii souborů z [`HistorySource`]
+/// a dopočítá všechny vazby mezi vyučujícími, předměty, články a tagy.
+/// Kontext sestavený z [`crate::sources::MemoryCards`]
+/// a [`crate::sources::MemoryHistory`] nepotřebuje knihu ani repozitář
+pub struct CatContextBuilder<C, H> {
+	/// zdroj karet
+	pub cards:   C,
+	/// zdroj historie souborů
+	pub history: H,
+	/// konfigurace `cat-prepu`
+	pub config:  CatConfig,
+	/// okamžik, ke kterému se posuzuje zastaralost článků,
+	/// pokud není nastaven, použije se aktuální čas
+	pub now:     Option<DateTime<Local>>,
+}
+
+impl<C: CardSource, H: HistorySource> CatContextBuilder<C, H> {
+	/// vytvoří sestavovač s výchozí konfigurací
+	pub fn new(cards: C, history: H) -> Self {
+		CatContextBuilder { cards, history, config: CatConfig::default(), now: None }
+	}
+
+	/// nastaví konfiguraci
+	pub fn config(mut self, config: &CatConfig) -> Self {
+		self.config = config.clone();
+		self
+	}
+
+	/// nastaví okamžik, ke kterému se posuzuje zastaralost článků
+	pub fn now(mut self, now: DateTime<Local>) -> Self {
+		self.now = Some(now);
+		self
+	}
+
+	/// sestaví kontext
+	///
+	/// chyby jednotlivých karet a souborů se sbírají, aby se
+	/// vypsaly všechny najednou, vrací se první z nich
+	pub fn build(mut self) -> Result<CatContext, CatError> {
+		let config = self.config.clone();
+		let history = &mut self.history;
+
+		let mut teacher_cards = self.cards.teacher_cards()?;
 		teacher_cards.sort_by(|a, b| a.jmeno.cmp(&b.jmeno));
 		let mut errors: Vec<_> = vec![];
 
-		eprintln!("{:?}", teacher_cards);
-
 		let mut teachers = teacher_cards
 			.iter()
 			.filter_map(|x| {
-    			let (status, files_created, error) = sh!(
-	    			"git whatchanged --author=\"{}\\|{}\\|{}\" --diff-filter=A --no-commit-id --name-only  | ( xargs ls -d || true ) | xargs -n 1 realpath --relative-to=src", x.jmeno,
-	    			x.email, x.username);
-
-    			if status != 0 {
-        			errors.push(CatError::CommandFailed { status, error, name: "git".into() });
-        			return None;
-    			}
-
-    			Some(Teacher {
-	    			card: x.clone(),
-	    			subjects: vec![],
-	    			files_created: files_created
-	    				.lines()
-	    				.map(PathBuf::from)
-	    				.collect::<Vec<_>>(),
-					articles:  vec![],
-    			})
+				let files = history
+					.files_created(x)
+					.and_then(|c| history.files_modified(x).map(|m| (c, m)));
+
+				let (files_created, files_modified) = match files {
+					Ok(f) => f,
+					Err(e) => {
+						errors.push(e);
+						return None;
+					}
+				};
+
+				Some(Teacher {
+					card: x.clone(),
+					subjects: vec![],
+					roles: vec![],
+					files_created,
+					files_modified,
+					articles: vec![],
+				})
 			})
 			.collect::<Vec<_>>();
 
-		if !errors.is_empty() {
-			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
-
-			return Err(errors[0].clone());
-		}
-
-		let subject_items = src
-			.iter()
-			.filter_map(|x| if let BookItem::Chapter(c) = x { Some(c) } else { None })
-			.filter(|x| x.path.clone().unwrap().to_str().unwrap().ends_with("subject.md"))
-			.cloned()
-			.collect::<Vec<_>>();
+		let mut subject_cards = self.cards.subject_cards()?;
 
-		let mut subject_cards = vec![];
-
-		src.for_each_mut(|x| {
-			if let BookItem::Chapter(c) = x {
-				if subject_items.contains(c) {
-					let (header, body) = match extract_header(&c.content) {
-						Ok(hb) => hb,
-						Err(e) => {
-							errors.push(e);
-							return;
-						}
-					};
-					c.content = body;
-
-					let mut card: SubjectCard = match toml::de::from_str(&header) {
-						Ok(c) => c,
-						Err(e) => {
-							errors.push(CatError::InvalidHeaderFormat { err: e });
-							return;
-						}
-					};
-
-					card._resolved_path = c.path.clone();
-
-					subject_cards.push(card);
-				}
+		subject_cards.retain(|x| {
+			if x._resolved_path.is_none() {
+				errors.push(CatError::UnresolvedCard { nazev: x.nazev.clone() });
 			}
-		});
-
-		if !errors.is_empty() {
-			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
 
-			return Err(errors[0].clone());
-		}
+			x._resolved_path.is_some()
+		});
 		subject_cards.sort_by(|a, b| a.nazev.cmp(&b.nazev));
 
 		let mut subjects = subject_cards
@@ -243,87 +429,114 @@ impl CatContext {
 				card:            x.clone(),
 				articles:        vec![],
 				resolved_author: None,
+				teachers:        vec![],
+				stats:           ArticleStats::default(),
 			})
 			.collect::<Vec<_>>();
 		subjects.sort_by(|a, b| a.card.nazev.cmp(&b.card.nazev));
 
 		let mut article_cards = vec![];
-
-		src.for_each_mut(|x| {
-			if let BookItem::Chapter(c) = x {
-				if subjects.iter().any(|y| {
-					let path = c.path.clone().unwrap();
-
-					path.starts_with(&y.path_root)
-						&& path.file_name().map(|x| x.to_str().unwrap())
-							!= Some("subject.md")
-				}) {
-					let (header, body) = match extract_header(&c.content) {
-						Ok(hb) => hb,
-						Err(e) => {
-							errors.push(e);
-							return;
-						}
-					};
-					c.content = body;
-
-					let mut card: ArticleCard = match toml::de::from_str(&header) {
-						Ok(c) => c,
-						Err(e) => {
-							errors.push(CatError::InvalidHeaderFormat { err: e });
-							return;
-						}
-					};
-
-					card._resolved_path = Some(c.path.clone().unwrap());
-
-					article_cards.push(card);
+		let mut article_stats = HashMap::new();
+
+		for (card, content) in self.cards.article_cards(&subject_cards)? {
+			let path = match &card._resolved_path {
+				Some(p) => p.clone(),
+				None => {
+					errors.push(CatError::UnresolvedCard { nazev: card.nazev });
+					continue;
 				}
-			}
-		});
+			};
+
+			article_stats.insert(path, stats::article_stats(&content));
+			article_cards.push(card);
+		}
+
+		let summary_order = article_cards
+			.iter()
+			.map(|x| x._resolved_path.clone().unwrap())
+			.collect::<Vec<_>>();
 		article_cards.sort_by(|a, b| a.nazev.cmp(&b.nazev));
 
-		if !errors.is_empty() {
-			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+		let tag_cards = self.cards.tag_cards()?;
+		let vocabulary = self.cards.vocabulary()?;
+		let allowed_tags = vocabulary.as_ref().map(|x| x.all_tags()).unwrap_or_default();
+		tags::canonicalize_tags(&mut article_cards, &tag_cards, &allowed_tags);
 
-			return Err(errors[0].clone());
+		if vocabulary.is_some() {
+			let unknown = tags::check_vocabulary(&article_cards, &allowed_tags);
+
+			if config.tag_vocabulary_mode == VocabularyMode::Deny {
+				errors.extend(unknown);
+			} else {
+				unknown.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+			}
 		}
 
+		let now = self.now.unwrap_or_else(Local::now);
+
 		let mut articles = article_cards
 			.iter()
 			.filter_map(|x| {
-				let (status, last_modified, error) = sh!(
-					"{}",
-					&format!(
-						"git log -1 --pretty=\"format:%ci\" -- src/'{}'",
-						x._resolved_path.clone().unwrap().display()
-					)
-				);
-
-				if status != 0 {
-					errors.push(CatError::CommandFailed {
-						status,
-						error,
-						name: "git".into(),
-					});
-					return None;
+				let path = x._resolved_path.clone().unwrap();
+				let file = match history.file_history(&path) {
+					Ok(f) => f,
+					Err(e) => {
+						errors.push(e);
+						return None;
+					}
+				};
+
+				let mut article_subjects = subjects
+					.iter()
+					.enumerate()
+					.filter(|(_, y)| path.starts_with(&y.path_root))
+					.map(|(i, _)| i)
+					.take(1)
+					.collect::<Vec<_>>();
+
+				for value in &x.predmet {
+					let found = subjects
+						.iter()
+						.enumerate()
+						.filter(|(_, y)| y.matches(value))
+						.map(|(i, _)| i)
+						.collect::<Vec<_>>();
+
+					match found.as_slice() {
+						[] => errors.push(CatError::UnknownArticleSubject {
+							subject: value.clone(),
+							path:    path.display().to_string(),
+						}),
+						[i] if !article_subjects.contains(i) => article_subjects.push(*i),
+						[_] => (),
+						_ => errors.push(CatError::AmbiguousArticleSubject {
+							subject:    value.clone(),
+							path:       path.display().to_string(),
+							candidates: found
+								.iter()
+								.map(|&i| subjects[i].path.display().to_string())
+								.collect::<Vec<_>>()
+								.join(", "),
+						}),
+					}
 				}
 
-				let (status, modified_by, error) = sh!(
-					"{}",
-					&format!(
-						"git log -s -n1 --pretty='format:%an' -- src/'{}'",
-						x._resolved_path.clone().unwrap().display()
-					)
-				);
-
-				if status != 0 {
-					errors.push(CatError::CommandFailed {
-						status,
-						error,
-						name: "git".into(),
-					});
-					return None;
+				let subject_cards = article_subjects
+					.iter()
+					.map(|&i| subjects[i].card.clone())
+					.collect::<Vec<_>>();
+
+				if let Some(datum) = &x.revidovat_do {
+					if parse_datum(datum).is_none() {
+						eprintln!(
+							"[cat-prep] warning: {}",
+							CatError::InvalidDate {
+								field: "revidovat_do".into(),
+								value: datum.clone(),
+								path:  path.display().to_string(),
+							}
+						);
+					}
 				}
 
 				let a = Article {
@@ -335,64 +548,79 @@ impl CatContext {
 						})
 						.map(|y| y.card.jmeno.clone())
 						.unwrap_or("Neznámý".into()),
-					modified_by,
-					last_modified,
+					modified_by: file.modified_by,
+					last_modified: file.last_modified,
+					modified_timestamp: file.modified_timestamp,
+					created_timestamp: file.created_timestamp.or(file.modified_timestamp),
 					path: x._resolved_path.clone().unwrap(),
 					modified_resolved: None,
 					resolved_author: teachers
 						.iter()
 						.find(|y| y.files_created.contains(&x._resolved_path.clone().unwrap()))
 						.map(|y| y.card.clone()),
-					subject_card: None,
+					subject_card: subject_cards.first().cloned(),
+					subject_cards,
+					stats: article_stats
+						.get(x._resolved_path.as_ref().unwrap())
+						.cloned()
+						.unwrap_or_default(),
+					stale: is_stale(x, file.modified_timestamp, config.max_stari, now),
 				};
 
-				subjects
-					.iter_mut()
-					.find(|y| x._resolved_path.clone().unwrap().starts_with(&y.path_root))
-					.map(|y| y.articles.push(a.clone()));
+				article_subjects.iter().for_each(|&i| subjects[i].articles.push(a.clone()));
 
 				Some(a)
 			})
 			.collect::<Vec<_>>();
 
-		if !errors.is_empty() {
-			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
-
-			return Err(errors[0].clone());
-		}
-
-		subjects.iter().for_each(|x| {
-			if teachers
-				.iter_mut()
-				.find(|y| x.articles.iter().any(|z| y.files_created.contains(&z.path)))
-				.map(|y| y.subjects.push(x.clone()))
-				.is_some()
-			{
-				return;
-			}
-
-			for a in &x.articles {
-				if teachers
-					.iter_mut()
-					.find(|y| y.files_created.contains(&a.path))
-					.map(|y| y.subjects.push(x.clone()))
-					.is_some()
-				{
-					return;
-				}
-			}
+		subjects.iter_mut().for_each(|x| {
+			sort_articles(&mut x.articles, x.card.razeni, &summary_order);
+			x.stats = stats::total_stats(&x.articles);
 		});
 
 		subjects.iter_mut().for_each(|x| {
-			if let Some(t) = teachers.iter().find(|t| {
-				x.card.zodpovedna_osoba == t.card.username
-					|| x.card.zodpovedna_osoba == t.card.jmeno
-					|| x.card.zodpovedna_osoba == t.card.email
-			}) {
+			if let Some(t) = teachers.iter().find(|t| t.card.matches(&x.card.zodpovedna_osoba)) {
 				x.resolved_author = Some(t.card.clone());
 			}
 		});
 
+		let subject_roles = subject_roles(&subjects, &teachers);
+
+		subjects.iter_mut().for_each(|x| {
+			x.teachers = teachers
+				.iter()
+				.filter_map(|t| {
+					let roles = subject_roles
+						.iter()
+						.filter(|r| r.subject == x.path && r.username == t.card.username)
+						.map(|r| r.role)
+						.collect::<Vec<_>>();
+
+					if roles.is_empty() {
+						None
+					} else {
+						Some(SubjectTeacher { card: t.card.clone(), roles })
+					}
+				})
+				.collect();
+			x.teachers.sort_by_key(|t| t.roles[0]);
+		});
+
+		teachers.iter_mut().for_each(|t| {
+			t.roles = subject_roles
+				.iter()
+				.filter(|r| r.username == t.card.username)
+				.cloned()
+				.collect();
+
+			let roles = &t.roles;
+			t.subjects = subjects
+				.iter()
+				.filter(|s| roles.iter().any(|r| r.subject == s.path))
+				.cloned()
+				.collect();
+		});
+
 		articles.iter().for_each(|x| {
 			let _ = teachers
 				.iter_mut()
@@ -400,23 +628,50 @@ impl CatContext {
 				.map(|y| y.articles.push(x.clone()));
 		});
 
+		// články vyučujícího jsou seřazeny podle předmětů
+		// a v rámci předmětu podle jeho řazení
+		teachers.iter_mut().for_each(|t| {
+			t.articles.sort_by_key(|a| {
+				subjects
+					.iter()
+					.enumerate()
+					.find_map(|(i, s)| {
+						s.articles.iter().position(|x| x.path == a.path).map(|j| (i, j))
+					})
+					.unwrap_or((usize::MAX, usize::MAX))
+			})
+		});
+
 		articles.iter_mut().for_each(|x| {
-			if let Some(t) = teachers.iter().find(|t| {
-				x.modified_by == t.card.username
-					|| x.modified_by == t.card.jmeno
-					|| x.modified_by == t.card.email
-			}) {
+			if let Some(t) = teachers.iter().find(|t| t.card.matches(&x.modified_by)) {
 				x.modified_resolved = Some(t.card.clone());
 			}
+		});
 
-			x.subject_card = subjects
-				.iter()
-				.find(|y| x.path.starts_with(&y.path_root))
-				.map(|y| y.card.clone());
-			//↑ should never be None because to be considered
-			// an article, there needs to be a subject prefix
+		let tag_map = tags::collect_tags(&article_cards);
+		let tag_slugs = render::tag_slugs(tag_map.keys());
+
+		let mut renamed = tag_slugs
+			.iter()
+			.filter(|(k, v)| **v != render::slugify(k))
+			.collect::<Vec<_>>();
+		renamed.sort();
+		renamed.iter().for_each(|(k, v)| {
+			eprintln!(
+				"[cat-prep] warning: tag '{}' has the same slug as another tag, using '{}'",
+				k, v
+			)
 		});
 
+		let teacher_slugs =
+			render::teacher_slugs(teacher_cards.iter().map(|t| &t.username));
+
+		if !errors.is_empty() {
+			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+
+			return Err(errors[0].clone());
+		}
+
 		Ok(CatContext {
 			teacher_cards,
 			teachers,
@@ -424,15 +679,140 @@ impl CatContext {
 			subjects,
 			article_cards: article_cards.clone(),
 			articles,
-			tags: article_cards.iter().fold(HashMap::new(), {
-				|mut acc, x| {
-					x.tagy.iter().for_each(|y| {
-						acc.entry(y.into()).or_insert(vec![]).push(x.clone())
-					});
-
-					acc
-				}
-			}),
+			subject_roles,
+			tag_tree: tags::tag_tree(&tag_map),
+			tag_slugs,
+			teacher_slugs,
+			tags: tag_map,
+			tag_cards,
+			vocabulary,
+			config,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sources::{MemoryCards, MemoryHistory};
+
+	fn teacher(username: &str, jmeno: &str) -> TeacherCard {
+		TeacherCard {
+			jmeno:    jmeno.into(),
+			email:    format!("{}@gjk.cz", username),
+			username: username.into(),
+			bio:      String::new(),
+		}
+	}
+
+	fn subject(path: &str, header: &str) -> SubjectCard {
+		let mut card: SubjectCard = toml::de::from_str(header).unwrap();
+		card._resolved_path = Some(path.into());
+		card
+	}
+
+	fn article(path: &str, header: &str) -> (ArticleCard, String) {
+		let mut card: ArticleCard = toml::de::from_str(header).unwrap();
+		card._resolved_path = Some(path.into());
+		(card, "text článku".into())
+	}
+
+	fn paths(articles: &[Article]) -> Vec<&Path> {
+		articles.iter().map(|a| a.path.as_path()).collect()
+	}
+
+	fn username(card: &Option<TeacherCard>) -> Option<&str> {
+		card.as_ref().map(|x| x.username.as_str())
+	}
+
+	#[test]
+	fn builds_context_from_memory_sources() {
+		let cards = MemoryCards {
+			teacher_cards: vec![
+				teacher("alice", "Alice Nováková"),
+				teacher("bob", "Bob Svoboda"),
+				teacher("carol", "Karolína Dvořáková"),
+			],
+			subject_cards: vec![
+				subject(
+					"mat/subject.md",
+					"nazev = \"Matematika\"\nzodpovedna_osoba = \"alice\"\nbio = \"\"",
+				),
+				subject(
+					"fyz/subject.md",
+					"nazev = \"Fyzika\"\nzodpovedna_osoba = \"Bob Svoboda\"\nbio = \"\"",
+				),
+			],
+			article_cards: vec![
+				article("mat/zlomky.md", "nazev = \"Zlomky\"\ntagy = [\"algebra\"]"),
+				article(
+					"fyz/pohyb.md",
+					"nazev = \"Pohyb\"\ntagy = [\"Algebra\", \"mechanika\"]\n\
+					 predmet = \"matematika\"",
+				),
+			],
+			..MemoryCards::default()
+		};
+
+		let mut history = MemoryHistory::default();
+		history.files_created.insert("alice".into(), vec!["mat/subject.md".into()]);
+		history.files_created.insert("bob".into(), vec!["fyz/pohyb.md".into()]);
+		history.files_modified.insert("carol".into(), vec!["mat/zlomky.md".into()]);
+
+		let context = CatContextBuilder::new(cards, history).build().unwrap();
+
+		let mat = context.subjects.iter().find(|s| s.card.nazev == "Matematika").unwrap();
+		let fyz = context.subjects.iter().find(|s| s.card.nazev == "Fyzika").unwrap();
+		assert_eq!(
+			paths(&mat.articles),
+			[Path::new("fyz/pohyb.md"), Path::new("mat/zlomky.md")]
+		);
+		assert_eq!(paths(&fyz.articles), [Path::new("fyz/pohyb.md")]);
+		assert_eq!(username(&mat.resolved_author), Some("alice"));
+
+		let pohyb = context.articles.iter().find(|a| a.card.nazev == "Pohyb").unwrap();
+		assert_eq!(pohyb.card.predmet, ["matematika"]);
+		assert_eq!(context.subject_of(pohyb).map(|s| &s.path), Some(&fyz.path));
+		assert_eq!(
+			pohyb.subject_cards.iter().map(|s| s.nazev.as_str()).collect::<Vec<_>>(),
+			["Fyzika", "Matematika"]
+		);
+		assert_eq!(username(&pohyb.resolved_author), Some("bob"));
+
+		let roles = |username: &str, subject: &Subject| {
+			context
+				.subject_roles
+				.iter()
+				.filter(|r| r.username == username && r.subject == subject.path)
+				.map(|r| r.role)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(roles("alice", mat), [Role::Responsible, Role::Author]);
+		assert_eq!(roles("bob", mat), [Role::Author]);
+		assert_eq!(roles("bob", fyz), [Role::Responsible, Role::Author]);
+		assert_eq!(roles("carol", mat), [Role::Contributor]);
+		assert_eq!(roles("carol", fyz), []);
+
+		let carol = context.teachers.iter().find(|t| t.card.username == "carol").unwrap();
+		let contributed = carol.subjects_with(Role::Contributor);
+		assert_eq!(contributed.len(), 1);
+		assert_eq!(contributed[0].path, mat.path);
+
+		let tagged = |tag: &str| {
+			let mut res = context.tags[tag]
+				.iter()
+				.filter_map(|a| a._resolved_path.clone())
+				.collect::<Vec<_>>();
+			res.sort();
+			res
+		};
+		// `algebra` a `Algebra` jsou jeden tag,
+		// při shodě počtu vyhrává abecedně první zápis
+		assert_eq!(
+			tagged("Algebra"),
+			[Path::new("fyz/pohyb.md"), Path::new("mat/zlomky.md")]
+		);
+		assert_eq!(tagged("mechanika"), [Path::new("fyz/pohyb.md")]);
+		assert!(!context.tags.contains_key("algebra"));
+	}
+}
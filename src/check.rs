@@ -0,0 +1,143 @@
+//! modul obsahující kontrolu knihy bez jejího sestavení
+//!
+//! Kontrola načte knihu pomocí `mdbooku`, sestaví z ní [`CatContext`]
+//! a nanečisto připraví a aplikuje všechny rendery na knihu v paměti.
+//! Na disk se nic nezapisuje, feedy se negenerují
+//! a chybějící kapitoly se nezakládají. Zástupné kapitoly
+//! generovaných stránek (např. `- [Tagy](tags.md)`)
+//! nemusí mít soubor.
+//!
+//! Chyby jsou vypisovány stejně jako při sestavování knihy
+//! (všechny najednou, ne pouze první z nich),
+//! kontrolu je tedy možné spouštět např. v pre-commit hooku
+//! nebo v CI bez nutnosti sestavovat celou knihu.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mdbook::book::{parse_summary, SummaryItem};
+use mdbook::{BookItem, Config, MDBook};
+
+use crate::cat_context::CatContext;
+use crate::config::CatConfig;
+use crate::error::CatError;
+use crate::render;
+
+/// výsledek úspěšné kontroly
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+	/// počet vyučujících
+	pub teachers: usize,
+	/// počet předmětů
+	pub subjects: usize,
+	/// počet článků
+	pub articles: usize,
+	/// počet tagů
+	pub tags:     usize,
+}
+
+/// změní zástupné kapitoly generovaných stránek, které nemají
+/// soubor, na rozpracované, a vrátí jejich názvy a cesty
+fn detach_placeholders(items: &mut [SummaryItem], src: &Path) -> Vec<(String, PathBuf)> {
+	let mut res = vec![];
+
+	for item in items.iter_mut() {
+		if let SummaryItem::Link(link) = item {
+			let missing = match &link.location {
+				Some(p) => render::is_generated_page(p) && !src.join(p).exists(),
+				None => false,
+			};
+
+			if missing {
+				res.extend(link.location.take().map(|p| (link.name.clone(), p)));
+			}
+
+			res.extend(detach_placeholders(&mut link.nested_items, src));
+		}
+	}
+
+	res
+}
+
+/// načte knihu v dané složce a sestaví z ní [`CatContext`]
+///
+/// `cat-prep` pracuje s cestami relativními ke kořeni knihy,
+/// proto tato funkce mění pracovní adresář procesu na `root`.
+/// Konfigurace se čte pro HTML renderer.
+///
+/// Kniha se načítá s vypnutým `create-missing`, aby načtení
+/// nezaložilo chybějící kapitoly ze `SUMMARY.md` na disku.
+/// Zástupné kapitoly generovaných stránek bez souboru se načtou
+/// jako prázdné, aby je `cat-prep` mohl vyplnit.
+///
+/// Tuto funkci využívají i ostatní příkazy, které
+/// pracují s knihou mimo `mdbook`
+pub fn load(root: &Path) -> Result<(MDBook, CatContext), CatError> {
+	let err = |e: mdbook::errors::Error| CatError::BookLoadFailed {
+		path:  root.display().to_string(),
+		error: e.to_string(),
+	};
+
+	let config_path = root.join("book.toml");
+	let mut config = if config_path.exists() {
+		Config::from_disk(&config_path).map_err(err)?
+	} else {
+		Config::default()
+	};
+	config.update_from_env();
+	config.build.create_missing = false;
+
+	let src = root.join(&config.book.src);
+	let summary_path = src.join("SUMMARY.md");
+	let summary = fs::read_to_string(&summary_path).map_err(|e| CatError::BookLoadFailed {
+		path:  summary_path.display().to_string(),
+		error: e.to_string(),
+	})?;
+	let mut summary = parse_summary(&summary).map_err(err)?;
+
+	let mut placeholders = vec![];
+	for items in vec![
+		&mut summary.prefix_chapters,
+		&mut summary.numbered_chapters,
+		&mut summary.suffix_chapters,
+	] {
+		placeholders.extend(detach_placeholders(items, &src));
+	}
+
+	let mut md =
+		MDBook::load_with_config_and_summary(root, config, summary).map_err(err)?;
+
+	// zástupné kapitoly se vyplňují podle cesty
+	md.book.for_each_mut(|item| match item {
+		BookItem::Chapter(c) if c.path.is_none() => {
+			c.path = placeholders.iter().find(|(n, _)| *n == c.name).map(|x| x.1.clone());
+		}
+		_ => (),
+	});
+
+	env::set_current_dir(&md.root).map_err(|e| CatError::BookLoadFailed {
+		path:  md.root.display().to_string(),
+		error: e.to_string(),
+	})?;
+
+	let config = CatConfig::from_book_config(&md.config, "html")?;
+	let context = CatContext::with_book(&mut md.book, &config)?;
+
+	Ok((md, context))
+}
+
+/// zkontroluje knihu v dané složce, viz [`load`]
+pub fn check(root: &Path) -> Result<CheckReport, CatError> {
+	let (mut md, context) = load(root)?;
+
+	let renders = render::create_renders(&context, &mut md.book)?;
+	render::execute_renders(renders, &mut md.book)?;
+
+	Ok(CheckReport {
+		teachers: context.teachers.len(),
+		subjects: context.subjects.len(),
+		articles: context.articles.len(),
+		tags:     context.tags.len(),
+	})
+}
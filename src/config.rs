@@ -0,0 +1,272 @@
+//! modul obsahující konfiguraci `cat-prepu`
+//!
+//! Konfigurace se čte ze sekce `[preprocessor.cat-prep]`
+//! souboru `book.toml`. Všechny hodnoty jsou nepovinné,
+//! chybějící hodnoty jsou nahrazeny výchozími.
+//!
+//! ```toml
+//! [preprocessor.cat-prep]
+//! tag-pages = true
+//! teacher-pages = true
+//! recent-changes = 10
+//! breadcrumbs = true
+//! subject-navigation = false
+//! feed = true
+//! feed-rss = true
+//! site-url = "https://gjk-cat.github.io/wiki/"
+//! export = "catalog.json"
+//! max-stari = 365
+//! tag-vocabulary = "vocabulary.toml"
+//! tag-vocabulary-mode = "deny"
+//! standalone = ["obecne"]
+//!
+//! [preprocessor.cat-prep.placement]
+//! subjects = "prefix"
+//! tags = "after:Úvod"
+//! recent = "under:O wiki"
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::PathBuf;
+
+use mdbook::Config;
+use serde::{Serialize, Deserialize};
+
+use crate::error::CatError;
+
+/// název sekce v `book.toml`, ve které
+/// se nachází konfigurace `cat-prepu`
+pub static CONFIG_SECTION: &'static str = "cat-prep";
+
+/// Konfigurace `cat-prepu`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CatConfig {
+	/// pokud je nastaveno, vygeneruje se pro každý tag
+	/// vlastní stránka `tags/<slug>.md` a stránka `tags.md`
+	/// bude obsahovat pouze oblak tagů
+	pub tag_pages:           bool,
+	/// pokud je nastaveno, vygeneruje se pro každého vyučujícího
+	/// vlastní stránka `teachers/<slug usernamu>.md` a stránka `teachers.md`
+	/// bude obsahovat pouze jejich seznam
+	pub teacher_pages:       bool,
+	/// adresa, na které je kniha nasazena.
+	/// Pokud je nastavena, všechny odkazy jsou absolutní,
+	/// jinak jsou relativní k dané kapitole
+	pub site_url:            Option<String>,
+	/// počet materiálů na stránce nedávných změn,
+	/// hodnota `0` stránku vypne
+	pub recent_changes:      usize,
+	/// zobrazit nad článkem drobečkovou navigaci
+	/// (Předměty › předmět › článek)
+	pub breadcrumbs:         bool,
+	/// zobrazit pod článkem odkazy na předchozí a další
+	/// materiál v rámci předmětu
+	pub subject_navigation:  bool,
+	/// pokud je nastaveno, vygenerují se Atom feedy nových
+	/// a upravených materiálů, viz modul [`crate::feed`].
+	/// Vyžaduje nastavenou hodnotu `site-url`
+	pub feed:                bool,
+	/// kromě Atom feedů generovat i RSS 2.0 feedy
+	pub feed_rss:            bool,
+	/// složka uvnitř `src`, do které se feedy zapisují
+	pub feed_dir:            String,
+	/// maximální počet položek v jednom feedu
+	pub feed_entries:        usize,
+	/// počet dní od poslední změny, po kterých je materiál
+	/// považován za zastaralý, lze přebít v headeru
+	/// materiálu hodnotou `revidovat_po`
+	pub max_stari:           Option<u64>,
+	/// soubor uvnitř `src`, do kterého se při sestavování
+	/// zapíše katalog materiálů v JSONu, viz [`crate::export`]
+	pub export:              Option<String>,
+	/// soubor se slovníkem povolených tagů, relativní ke kořeni knihy,
+	/// viz [`crate::models::TagGroup`]
+	pub tag_vocabulary:      Option<String>,
+	/// jak naložit s tagy mimo slovník
+	pub tag_vocabulary_mode: VocabularyMode,
+	/// složky uvnitř `src`, jejichž kapitoly mimo předměty
+	/// jsou samostatnými články (bez předmětu)
+	pub standalone:          Vec<String>,
+	/// umístění vygenerovaných kapitol v knize
+	pub placement:           PlacementConfig,
+	/// název knihy, není součástí sekce `cat-prepu`,
+	/// přebírá se z `[book]`
+	#[serde(skip_deserializing)]
+	pub title:               Option<String>,
+	/// zdrojová složka knihy relativní ke kořeni knihy,
+	/// není součástí sekce `cat-prepu`, přebírá se z `[book]`
+	#[serde(skip_deserializing)]
+	pub src:                 PathBuf,
+	/// renderer, pro který je kniha připravována,
+	/// není součástí `book.toml`, dodává jej `mdbook`
+	#[serde(skip_deserializing)]
+	pub renderer:            String,
+}
+
+impl Default for CatConfig {
+	fn default() -> Self {
+		CatConfig {
+			tag_pages:           false,
+			teacher_pages:       false,
+			site_url:            None,
+			recent_changes:      10,
+			breadcrumbs:         false,
+			subject_navigation:  true,
+			feed:                false,
+			feed_rss:            false,
+			feed_dir:            "feeds".into(),
+			feed_entries:        50,
+			max_stari:           None,
+			export:              None,
+			tag_vocabulary:      None,
+			tag_vocabulary_mode: VocabularyMode::Warn,
+			standalone:          vec![],
+			placement:           PlacementConfig::default(),
+			title:               None,
+			src:                 PathBuf::from("src"),
+			renderer:            "html".into(),
+		}
+	}
+}
+
+/// způsob kontroly tagů proti slovníku
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VocabularyMode {
+	/// tagy mimo slovník pouze vypíší varování
+	Warn,
+	/// tagy mimo slovník jsou chybou
+	Deny,
+}
+
+impl Default for VocabularyMode {
+	fn default() -> Self {
+		VocabularyMode::Warn
+	}
+}
+
+/// umístění vygenerované kapitoly v knize
+///
+/// V `book.toml` se zapisuje jako řetězec:
+/// - `"prefix"` - na začátek knihy
+/// - `"suffix"` - na konec knihy
+/// - `"after:<název>"` - za kapitolu s daným názvem
+/// - `"under:<název>"` - jako poslední podkapitola kapitoly s daným názvem
+///
+/// Pokud `SUMMARY.md` již obsahuje kapitolu se stejným souborem
+/// (např. `- [Tagy](tags.md)`) nebo rozpracovanou kapitolu se stejným
+/// názvem (např. `- [Tagy]()`), vygenerovaná kapitola ji nahradí
+/// a umístění se neuplatní
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Placement {
+	/// na začátek knihy
+	Prefix,
+	/// na konec knihy
+	Suffix,
+	/// za kapitolu s daným názvem
+	After(String),
+	/// pod kapitolu s daným názvem
+	Under(String),
+}
+
+impl Default for Placement {
+	fn default() -> Self {
+		Placement::Suffix
+	}
+}
+
+impl TryFrom<String> for Placement {
+	type Error = String;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		match value.trim() {
+			"prefix" => return Ok(Placement::Prefix),
+			"suffix" => return Ok(Placement::Suffix),
+			_ => (),
+		}
+
+		let (kind, name) = match value.find(':') {
+			Some(i) => (value[..i].trim(), value[i + 1..].trim()),
+			None => ("", ""),
+		};
+
+		match (kind, name) {
+			("after", n) if !n.is_empty() => Ok(Placement::After(n.to_string())),
+			("under", n) if !n.is_empty() => Ok(Placement::Under(n.to_string())),
+			_ => Err(format!(
+				"invalid placement '{}', expected prefix, suffix, after:<chapter> or under:<chapter>",
+				value
+			)),
+		}
+	}
+}
+
+impl From<Placement> for String {
+	fn from(value: Placement) -> Self {
+		value.to_string()
+	}
+}
+
+impl fmt::Display for Placement {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Placement::Prefix => write!(f, "prefix"),
+			Placement::Suffix => write!(f, "suffix"),
+			Placement::After(n) => write!(f, "after:{}", n),
+			Placement::Under(n) => write!(f, "under:{}", n),
+		}
+	}
+}
+
+/// umístění jednotlivých vygenerovaných kapitol,
+/// sekce `[preprocessor.cat-prep.placement]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlacementConfig {
+	/// umístění stránky předmětů (`subjects.md`)
+	pub subjects:    Placement,
+	/// umístění stránky vyučujících (`teachers.md`)
+	pub teachers:    Placement,
+	/// umístění stránky nedávných změn (`recent.md`)
+	pub recent:      Placement,
+	/// umístění stránky tagů (`tags.md`)
+	pub tags:        Placement,
+	/// umístění stránky údržby (`maintenance.md`)
+	pub maintenance: Placement,
+}
+
+impl CatConfig {
+	/// přečte konfiguraci z konfigurace `mdbooku`
+	/// pro daný renderer.
+	///
+	/// Pokud sekce `cat-prepu` chybí, je vrácena
+	/// výchozí konfigurace
+	pub fn from_book_config(
+		config: &Config,
+		renderer: &str,
+	) -> Result<CatConfig, CatError> {
+		let mut res: CatConfig = match config.get_preprocessor(CONFIG_SECTION) {
+			Some(table) => toml::Value::Table(table.clone())
+				.try_into()
+				.map_err(|err| CatError::InvalidConfig { err })?,
+			None => CatConfig::default(),
+		};
+
+		res.renderer = renderer.to_string();
+		res.title = config.book.title.clone();
+		res.src = config.book.src.clone();
+
+		Ok(res)
+	}
+
+	/// zda je kniha sestavována HTML rendererem
+	///
+	/// ostatní renderery (LaTeX, EPUB, ...) dostávají
+	/// čistý markdown bez HTML a skriptů
+	pub fn is_html(&self) -> bool {
+		self.renderer == "html"
+	}
+}
@@ -43,6 +43,141 @@ pub enum CatError {
 		/// chyba parsování
 		err: TomlError,
 	},
+	/// Soubor `tags.toml` nemá správný formát
+	#[fail(display = "invalid tags.toml: {}", err)]
+	InvalidTagCards {
+		/// chyba parsování
+		err: TomlError,
+	},
+	/// Slovník tagů nemá správný formát
+	#[fail(display = "invalid tag vocabulary {}: {}", path, err)]
+	InvalidVocabulary {
+		/// cesta ke slovníku
+		path: String,
+		/// chyba parsování
+		err:  TomlError,
+	},
+	/// Článek má tag, který není ve slovníku tagů
+	#[fail(display = "tag '{}' in {} is not in the vocabulary{}", tag, path, suggestion)]
+	UnknownTag {
+		/// neznámý tag
+		tag:        String,
+		/// cesta k článku
+		path:       String,
+		/// návrh podobných tagů ze slovníku, popř. prázdný
+		suggestion: String,
+	},
+	/// Konfigurace v `book.toml` nemá správný formát
+	#[fail(display = "invalid cat-prep configuration: {}", err)]
+	InvalidConfig {
+		/// chyba parsování
+		err: TomlError,
+	},
+	/// Feedy vyžadují absolutní adresy, a tedy nastavenou hodnotu `site-url`
+	#[fail(display = "feeds require the site-url option to be set")]
+	MissingSiteUrl,
+	/// kapitola, za nebo pod kterou se měla vložit
+	/// vygenerovaná kapitola, v knize neexistuje
+	#[fail(display = "cannot place chapter '{}': chapter '{}' not found", chapter, name)]
+	ChapterNotFound {
+		/// vkládaná kapitola
+		chapter: String,
+		/// hledaná kapitola
+		name:    String,
+	},
+	/// Nepodařilo se načíst knihu
+	#[fail(display = "failed to load book at {}: {}", path, error)]
+	BookLoadFailed {
+		/// cesta ke knize
+		path:  String,
+		/// chybová hláška `mdbooku`
+		error: String,
+	},
+	/// Nepodařilo se přečíst soubor
+	#[fail(display = "failed to read file {}: {}", path, error)]
+	ReadFailed {
+		/// cesta k souboru
+		path:  String,
+		/// chybová hláška
+		error: String,
+	},
+	/// zakládaný soubor již existuje
+	#[fail(display = "file {} already exists", path)]
+	AlreadyExists {
+		/// cesta k souboru
+		path: String,
+	},
+	/// datum v headeru článku nelze přečíst
+	#[fail(
+		display = "cannot parse {} '{}' in {}, expected e.g. 31.12.2024",
+		field, value, path
+	)]
+	InvalidDate {
+		/// název pole v headeru
+		field: String,
+		/// hodnota z headeru
+		value: String,
+		/// cesta k článku
+		path:  String,
+	},
+	/// username zakládaného vyučujícího je prázdný
+	/// a nelze jej odvodit ani ze jména
+	#[fail(display = "teacher username is empty, set it with --username or --name")]
+	MissingUsername,
+	/// karta předmětu nebo článku ze zdroje karet nemá cestu
+	#[fail(display = "card '{}' has no resolved path", nazev)]
+	UnresolvedCard {
+		/// název z karty
+		nazev: String,
+	},
+	/// předmět uvedený v `predmet` článku neexistuje
+	#[fail(display = "subject '{}' of article {} not found", subject, path)]
+	UnknownArticleSubject {
+		/// hodnota z headeru
+		subject: String,
+		/// cesta k článku
+		path:    String,
+	},
+	/// předmětu uvedenému v `predmet` článku odpovídá více předmětů,
+	/// např. dva předměty se stejným názvem
+	#[fail(
+		display = "subject '{}' of article {} is ambiguous, use the path of one of: {}",
+		subject, path, candidates
+	)]
+	AmbiguousArticleSubject {
+		/// hodnota z headeru
+		subject:    String,
+		/// cesta k článku
+		path:       String,
+		/// cesty k odpovídajícím předmětům, oddělené čárkou
+		candidates: String,
+	},
+	/// předmět s daným názvem nebo složkou neexistuje
+	#[fail(display = "subject '{}' not found", name)]
+	SubjectNotFound {
+		/// hledaný předmět
+		name: String,
+	},
+	/// kapitola s danou cestou není v `SUMMARY.md`
+	#[fail(display = "chapter {} not found in SUMMARY.md", path)]
+	SummaryEntryNotFound {
+		/// cesta ke kapitole
+		path: String,
+	},
+	/// Nepodařilo se převést katalog do JSONu
+	#[fail(display = "failed to export catalog: {}", error)]
+	ExportFailed {
+		/// chybová hláška
+		error: String,
+	},
+	/// Nepodařilo se zapsat vygenerovaný soubor
+	#[fail(display = "failed to write file {}: {}", path, error)]
+	WriteFailed {
+		/// cesta k souboru
+		path:  String,
+		/// chybová hláška
+		error: String,
+	},
 	/// Nepodařilo se spustit příkaz v shell,
 	/// nšbo došlo k chybě při běhu.
 	///
@@ -0,0 +1,289 @@
+//! modul obsahující export katalogu materiálů do JSONu
+//!
+//! Katalog je určen pro nástroje třetích stran (intranet, rozvrhy, ...)
+//! a na rozdíl od [`CatContext`] neobsahuje redundantní kopie
+//! objektů. Objekty na sebe odkazují pomocí identifikátorů:
+//! - vyučující pomocí `username`
+//! - předměty a články pomocí cesty ke svému souboru relativní ke `src`
+//! - tagy pomocí svého názvu
+//!
+//! Odkazy (`link`) jsou absolutní, pokud je nastavena hodnota
+//! `site-url`, jinak jsou relativní ke kořeni webu.
+//! Data jsou ve formátu RFC 3339.
+//!
+//! Při nekompatibilní změně struktury katalogu
+//! se zvýší [`FORMAT_VERSION`].
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "title": "Kočičí wiki",
+//!   "site_url": "https://gjk-cat.github.io/wiki/",
+//!   "teachers": [{ "username": "magnusi", "jmeno": "...", "subjects": ["predmety/p1/subject.md"], ... }],
+//!   "subjects": [{ "id": "predmety/p1/subject.md", "articles": ["predmety/p1/material1.md"], ... }],
+//!   "articles": [{ "id": "predmety/p1/material1.md", "subject": "predmety/p1/subject.md", "subjects": ["predmety/p1/subject.md"], ... }],
+//!   "tags": [{ "name": "tag1", "articles": ["predmety/p1/material1.md"], ... }],
+//!   "subject_roles": [{ "username": "magnusi", "subject": "predmety/p1/subject.md", "role": "author" }]
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+use crate::cat_context::CatContext;
+use crate::error::CatError;
+use crate::links::LinkBuilder;
+use crate::models::*;
+use crate::render::teacher_page;
+use crate::tags::{find_tag_card, parent_tag};
+use crate::util::write_if_changed;
+
+/// verze formátu katalogu
+pub const FORMAT_VERSION: u32 = 1;
+
+/// vyučující v katalogu
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogTeacher {
+	/// username, slouží jako identifikátor
+	pub username: String,
+	/// jméno
+	pub jmeno:    String,
+	/// email
+	pub email:    String,
+	/// bio v markdownu
+	pub bio:      String,
+	/// odkaz na profil vyučujícího
+	pub link:     String,
+	/// předměty, na kterých se vyučující podílel
+	pub subjects: Vec<PathBuf>,
+	/// články, které vyučující založil
+	pub articles: Vec<PathBuf>,
+}
+
+/// předmět v katalogu
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogSubject {
+	/// cesta k `subject.md`, slouží jako identifikátor
+	pub id:               PathBuf,
+	/// název předmětu
+	pub nazev:            String,
+	/// popis předmětu
+	pub bio:              String,
+	/// zodpovědná osoba, jak je uvedena v headeru
+	pub zodpovedna_osoba: String,
+	/// username zodpovědné osoby, pokud se ji podařilo najít
+	pub responsible:      Option<String>,
+	/// odkaz na stránku předmětu
+	pub link:             String,
+	/// články předmětu v pořadí daném jeho řazením
+	pub articles:         Vec<PathBuf>,
+	/// součet statistik článků
+	pub stats:            ArticleStats,
+}
+
+/// článek v katalogu
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogArticle {
+	/// cesta k článku, slouží jako identifikátor
+	pub id:          PathBuf,
+	/// název článku
+	pub nazev:       String,
+	/// odkaz na článek
+	pub link:        String,
+	/// hlavní předmět, pod který článek patří
+	pub subject:     Option<PathBuf>,
+	/// všechny předměty článku včetně hlavního
+	pub subjects:    Vec<PathBuf>,
+	/// tagy
+	pub tagy:        Vec<String>,
+	/// datum z headeru, libovolný formát
+	pub datum:       Option<String>,
+	/// pořadí z headeru
+	pub poradi:      Option<i64>,
+	/// autor (podle gitu)
+	pub author:      String,
+	/// username autora, pokud se jej podařilo najít
+	pub author_id:   Option<String>,
+	/// kdo článek naposledy upravil (podle gitu)
+	pub modified_by: String,
+	/// username posledního editora, pokud se jej podařilo najít
+	pub modified_id: Option<String>,
+	/// kdy byl článek vytvořen, `null`, pokud článek
+	/// ještě není v gitu
+	pub created:     Option<String>,
+	/// kdy byl článek naposledy upraven, `null`, pokud článek
+	/// ještě není v gitu
+	pub modified:    Option<String>,
+	/// statistiky obsahu
+	pub stats:       ArticleStats,
+	/// článek může být zastaralý
+	pub stale:       bool,
+}
+
+/// tag v katalogu
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogTag {
+	/// název tagu, slouží jako identifikátor
+	pub name:     String,
+	/// slug tagu
+	pub slug:     String,
+	/// odkaz na tag
+	pub link:     String,
+	/// nadřazený tag hierarchického tagu,
+	/// články tagu zahrnují i články jeho potomků
+	pub parent:   Option<String>,
+	/// popisek tagu z `tags.toml`
+	pub popis:    Option<String>,
+	/// barva tagu z `tags.toml`
+	pub barva:    Option<String>,
+	/// články s tímto tagem
+	pub articles: Vec<PathBuf>,
+}
+
+/// katalog materiálů
+#[derive(Debug, Clone, Serialize)]
+pub struct Catalog {
+	/// verze formátu, viz [`FORMAT_VERSION`]
+	pub format_version: u32,
+	/// název knihy
+	pub title:          Option<String>,
+	/// adresa, na které je kniha nasazena
+	pub site_url:       Option<String>,
+	/// vyučující seřazení podle jména
+	pub teachers:       Vec<CatalogTeacher>,
+	/// předměty seřazené podle názvu
+	pub subjects:       Vec<CatalogSubject>,
+	/// články seřazené podle názvu
+	pub articles:       Vec<CatalogArticle>,
+	/// role vyučujících v předmětech
+	pub subject_roles:  Vec<SubjectRole>,
+	/// tagy seřazené podle názvu
+	pub tags:           Vec<CatalogTag>,
+}
+
+/// převede unixový timestamp na datum ve formátu RFC 3339,
+/// `None` pro timestamp mimo rozsah, který `chrono` umí zobrazit
+fn date(timestamp: i64) -> Option<String> {
+	Utc.timestamp_opt(timestamp, 0).single().map(|x| x.to_rfc3339())
+}
+
+impl Catalog {
+	/// sestaví katalog z kontextu
+	pub fn new(context: &CatContext) -> Catalog {
+		let config = &context.config;
+		let links = LinkBuilder::new(config, Path::new(""));
+
+		let mut tags = context.tags.iter().collect::<Vec<_>>();
+		tags.sort_by(|a, b| a.0.cmp(b.0));
+
+		Catalog {
+			format_version: FORMAT_VERSION,
+			title:          config.title.clone(),
+			site_url:       config.site_url.clone(),
+			teachers:       context
+				.teachers
+				.iter()
+				.map(|t| CatalogTeacher {
+					username: t.card.username.clone(),
+					jmeno:    t.card.jmeno.clone(),
+					email:    t.card.email.clone(),
+					bio:      t.card.bio.clone(),
+					link:     if config.teacher_pages {
+						links.page(&teacher_page(&context.teacher_slug(&t.card.username)))
+					} else {
+						let slug = context.teacher_slug(&t.card.username);
+						format!("{}#{}", links.page(Path::new("teachers.md")), slug)
+					},
+					subjects: t.subjects.iter().map(|s| s.path.clone()).collect(),
+					articles: t.articles.iter().map(|a| a.path.clone()).collect(),
+				})
+				.collect(),
+			subjects:       context
+				.subjects
+				.iter()
+				.map(|s| CatalogSubject {
+					id:               s.path.clone(),
+					nazev:            s.card.nazev.clone(),
+					bio:              s.card.bio.clone(),
+					zodpovedna_osoba: s.card.zodpovedna_osoba.clone(),
+					responsible:      s.resolved_author.as_ref().map(|x| x.username.clone()),
+					link:             links.page(&s.path),
+					articles:         s.articles.iter().map(|a| a.path.clone()).collect(),
+					stats:            s.stats.clone(),
+				})
+				.collect(),
+			articles:       context
+				.articles
+				.iter()
+				.map(|a| CatalogArticle {
+					id:          a.path.clone(),
+					nazev:       a.card.nazev.clone(),
+					link:        links.page(&a.path),
+					subject:     a.subject_card.as_ref().and_then(|s| s._resolved_path.clone()),
+					subjects:    a
+						.subject_cards
+						.iter()
+						.filter_map(|s| s._resolved_path.clone())
+						.collect(),
+					tagy:        a.card.tagy.clone(),
+					datum:       a.card.datum.clone(),
+					poradi:      a.card.poradi,
+					author:      a.author.clone(),
+					author_id:   a.resolved_author.as_ref().map(|x| x.username.clone()),
+					modified_by: a.modified_by.clone(),
+					modified_id: a.modified_resolved.as_ref().map(|x| x.username.clone()),
+					created:     a.created_timestamp.and_then(date),
+					modified:    a.modified_timestamp.and_then(date),
+					stats:       a.stats.clone(),
+					stale:       a.stale,
+				})
+				.collect(),
+			subject_roles:  context.subject_roles.clone(),
+			tags:           tags
+				.into_iter()
+				.map(|(name, cards)| {
+					let card = find_tag_card(&context.tag_cards, name).cloned().unwrap_or_default();
+					let slug = context.tag_slug(name);
+
+					CatalogTag {
+						name:     name.clone(),
+						link:     if config.tag_pages {
+							links.page(&PathBuf::from(format!("tags/{}.md", slug)))
+						} else {
+							format!("{}#{}", links.page(Path::new("tags.md")), slug)
+						},
+						slug,
+						parent:   parent_tag(name).map(String::from),
+						popis:    card.popis,
+						barva:    card.barva,
+						articles: cards.iter().filter_map(|c| c._resolved_path.clone()).collect(),
+					}
+				})
+				.collect(),
+		}
+	}
+
+	/// převede katalog na JSON
+	pub fn to_json(&self) -> Result<String, CatError> {
+		serde_json::to_string_pretty(self)
+			.map_err(|e| CatError::ExportFailed { error: e.to_string() })
+	}
+}
+
+/// zapíše katalog do souboru nastaveného v konfiguraci (`export`)
+///
+/// cesta je relativní ke zdrojové složce knihy (viz [`CatConfig::src`]),
+/// aby se katalog dostal do výstupu HTML rendereru. Nedělá nic,
+/// pokud není export nastaven nebo se kniha nesestavuje HTML rendererem.
+///
+/// [`CatConfig::src`]: crate::config::CatConfig::src
+pub fn write_export(context: &CatContext) -> Result<(), CatError> {
+	let path = match &context.config.export {
+		Some(p) if context.config.is_html() => context.config.src.join(p),
+		_ => return Ok(()),
+	};
+
+	write_if_changed(&path, &Catalog::new(context).to_json()?)
+}
@@ -0,0 +1,329 @@
+//! modul obsahující generování Atom a RSS feedů
+//!
+//! Feedy obsahují nově vytvořené a upravené materiály,
+//! seřazené od naposledy změněného. Vytváří se tyto feedy
+//! (cesty jsou relativní ke složce `src`):
+//! - `<feed-dir>/all.atom.xml` - všechny materiály
+//! - `<feed-dir>/subjects/<složka předmětu>.atom.xml` - materiály jednoho předmětu
+//! - `<feed-dir>/tags/<slug>.atom.xml` - materiály s daným tagem
+//!
+//! Materiály, které ještě nejsou v gitu, ve feedech nejsou.
+//!
+//! Pokud je nastaveno `feed-rss`, vygeneruje se ke každému
+//! z nich i RSS 2.0 varianta s příponou `.rss.xml`.
+//!
+//! Feedy potřebují absolutní adresy, proto vyžadují nastavenou
+//! hodnotu `site-url`, a generují se pouze pro HTML renderer.
+//!
+//! Jelikož HTML renderer `mdbooku` před sestavením vyprázdní
+//! výstupní složku, jsou feedy zapsány do zdrojové složky knihy
+//! (`src` v `[book]`), odkud je renderer zkopíruje do výstupu
+//! spolu s ostatními soubory.
+//! Soubor se přepisuje pouze tehdy, pokud se jeho obsah změnil,
+//! aby `mdbook serve` nesestavoval knihu stále dokola.
+//! Doporučuje se tedy složku s feedy přidat do `.gitignore`.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tinytemplate::TinyTemplate;
+
+use crate::cat_context::CatContext;
+use crate::config::CatConfig;
+use crate::error::CatError;
+use crate::links::LinkBuilder;
+use crate::models::*;
+use crate::util::write_if_changed;
+
+/// položka feedu
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEntry {
+	/// název článku
+	pub title:           String,
+	/// absolutní odkaz na článek, slouží i jako jeho id
+	pub link:            String,
+	/// datum vytvoření ve formátu RFC 3339
+	pub published:       String,
+	/// datum poslední změny ve formátu RFC 3339
+	pub updated:         String,
+	/// datum poslední změny ve formátu RFC 2822 (pro RSS)
+	pub updated_rfc2822: String,
+	/// autor článku
+	pub author:          String,
+	/// název předmětu, pod který článek spadá
+	pub subject:         Option<String>,
+	/// tagy článku
+	pub categories:      Vec<String>,
+}
+
+/// jeden feed
+#[derive(Debug, Clone, Serialize)]
+pub struct Feed {
+	/// název feedu
+	pub title:           String,
+	/// cesta k feedu bez přípony, relativní ke složce `src`
+	pub path:            PathBuf,
+	/// absolutní odkaz na stránku, ke které feed patří
+	pub link:            String,
+	/// absolutní odkaz na Atom variantu feedu
+	pub atom_link:       String,
+	/// absolutní odkaz na RSS variantu feedu
+	pub rss_link:        String,
+	/// datum poslední změny ve formátu RFC 3339
+	pub updated:         String,
+	/// datum poslední změny ve formátu RFC 2822 (pro RSS)
+	pub updated_rfc2822: String,
+	/// položky feedu, od naposledy změněné
+	pub entries:         Vec<FeedEntry>,
+}
+
+/// cesta k hlavnímu feedu (bez přípony)
+pub fn main_feed(config: &CatConfig) -> PathBuf {
+	Path::new(&config.feed_dir).join("all")
+}
+
+/// cesta k feedu daného předmětu (bez přípony)
+///
+/// feed se jmenuje podle složky předmětu, ne podle názvu,
+/// aby měly i stejně pojmenované předměty každý svůj feed
+pub fn subject_feed(config: &CatConfig, subject: &SubjectCard) -> PathBuf {
+	let folder = subject._resolved_path.as_ref().and_then(|x| x.parent());
+
+	Path::new(&config.feed_dir).join("subjects").join(folder.unwrap_or(Path::new("")))
+}
+
+/// cesta k feedu tagu s daným slugem (bez přípony),
+/// viz [`CatContext::tag_slug`]
+pub fn tag_feed(config: &CatConfig, slug: &str) -> PathBuf {
+	Path::new(&config.feed_dir).join("tags").join(slug)
+}
+
+/// přidá k cestě feedu příponu daného formátu (`atom` nebo `rss`)
+pub fn with_format(path: &Path, format: &str) -> PathBuf {
+	let mut name = path.file_name().unwrap_or_default().to_os_string();
+	name.push(format!(".{}.xml", format));
+	path.with_file_name(name)
+}
+
+/// převede unixový timestamp na datum ve formátu RFC 3339 a RFC 2822,
+/// `None` pro timestamp mimo rozsah, který `chrono` umí zobrazit
+fn dates(timestamp: i64) -> Option<(String, String)> {
+	Utc.timestamp_opt(timestamp, 0).single().map(|x| (x.to_rfc3339(), x.to_rfc2822()))
+}
+
+impl Feed {
+	/// sestaví feed z daných článků
+	///
+	/// `page` je kapitola, ke které feed patří
+	pub fn new(
+		context: &CatContext,
+		title: String,
+		path: PathBuf,
+		page: Option<&str>,
+		articles: Vec<&Article>,
+	) -> Feed {
+		let links = LinkBuilder::new(&context.config, Path::new(""));
+
+		let mut articles = articles;
+		articles.sort_by_key(|a| Reverse(a.modified_timestamp.max(a.created_timestamp)));
+		articles.truncate(context.config.feed_entries);
+
+		// články bez historie v gitu a články, jejichž datum
+		// nelze zobrazit, ve feedu nejsou
+		let entries = articles
+			.into_iter()
+			.filter_map(|a| {
+				let (published, _) = dates(a.created_timestamp?)?;
+				let (updated, updated_rfc2822) = dates(a.modified_timestamp?)?;
+
+				Some(FeedEntry {
+					title: a.card.nazev.clone(),
+					link: links.link(&a.path),
+					published,
+					updated,
+					updated_rfc2822,
+					author: a.author.clone(),
+					subject: a.subject_card.as_ref().map(|s| s.nazev.clone()),
+					categories: a.card.tagy.clone(),
+				})
+			})
+			.collect::<Vec<_>>();
+
+		// prázdný feed má datum počátku unixového času
+		let (updated, updated_rfc2822) = match entries.first() {
+			Some(e) => (e.updated.clone(), e.updated_rfc2822.clone()),
+			None => dates(0).unwrap_or_default(),
+		};
+
+		Feed {
+			title,
+			link: match page {
+				Some(p) => links.link(Path::new(p)),
+				None => links.file(Path::new("")),
+			},
+			atom_link: links.file(&with_format(&path, "atom")),
+			rss_link: links.file(&with_format(&path, "rss")),
+			path,
+			updated,
+			updated_rfc2822,
+			entries,
+		}
+	}
+}
+
+/// šablona Atom feedu
+pub static ATOM_TEMPLATE: &'static str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+	<title>{title}</title>
+	<id>{atom_link}</id>
+	<link rel="self" href="{atom_link}"/>
+	<link rel="alternate" type="text/html" href="{link}"/>
+	<updated>{updated}</updated>
+{{ for e in entries }}	<entry>
+		<title>{e.title}</title>
+		<id>{e.link}</id>
+		<link rel="alternate" type="text/html" href="{e.link}"/>
+		<published>{e.published}</published>
+		<updated>{e.updated}</updated>
+		<author><name>{e.author}</name></author>
+{{ if e.subject }}		<summary>Předmět: {e.subject}</summary>
+{{ endif }}{{ for c in e.categories }}		<category term="{c}"/>
+{{ endfor }}	</entry>
+{{ endfor }}</feed>
+"#;
+
+/// šablona RSS 2.0 feedu
+pub static RSS_TEMPLATE: &'static str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/">
+	<channel>
+		<title>{title}</title>
+		<link>{link}</link>
+		<description>{title}</description>
+		<atom:link rel="self" type="application/rss+xml" href="{rss_link}"/>
+		<lastBuildDate>{updated_rfc2822}</lastBuildDate>
+{{ for e in entries }}		<item>
+			<title>{e.title}</title>
+			<link>{e.link}</link>
+			<guid isPermaLink="true">{e.link}</guid>
+			<pubDate>{e.updated_rfc2822}</pubDate>
+			<dc:creator>{e.author}</dc:creator>
+{{ if e.subject }}			<description>Předmět: {e.subject}</description>
+{{ endif }}{{ for c in e.categories }}			<category>{c}</category>
+{{ endfor }}		</item>
+{{ endfor }}	</channel>
+</rss>
+"#;
+
+/// formátovač escapující hodnoty pro XML
+///
+/// výchozí formátovač `tinytemplate` escapuje pro HTML,
+/// což by zbytečně znečitelnilo odkazy
+fn format_xml(value: &Value, output: &mut String) -> tinytemplate::error::Result<()> {
+	match value {
+		Value::String(s) => {
+			for c in s.chars() {
+				match c {
+					'&' => output.push_str("&amp;"),
+					'<' => output.push_str("&lt;"),
+					'>' => output.push_str("&gt;"),
+					'"' => output.push_str("&quot;"),
+					'\'' => output.push_str("&apos;"),
+					c => output.push(c),
+				}
+			}
+			Ok(())
+		}
+		_ => tinytemplate::format(value, output),
+	}
+}
+
+/// sestaví všechny feedy z kontextu
+pub fn create_feeds(context: &CatContext) -> Vec<Feed> {
+	let title = context.config.title.clone().unwrap_or_else(|| "Materiály".into());
+	let mut feeds = vec![];
+
+	feeds.push(Feed::new(
+		context,
+		title.clone(),
+		main_feed(&context.config),
+		if context.config.recent_changes > 0 { Some("recent.md") } else { None },
+		context.articles.iter().collect(),
+	));
+
+	context.subjects.iter().for_each(|s| {
+		let in_subject = |a: &&Article| {
+			a.subject_cards.iter().any(|x| x._resolved_path.as_ref() == Some(&s.path))
+		};
+
+		feeds.push(Feed::new(
+			context,
+			format!("{}: {}", title, s.card.nazev),
+			subject_feed(&context.config, &s.card),
+			s.path.to_str(),
+			context.articles.iter().filter(in_subject).collect(),
+		))
+	});
+
+	let mut tags = context.tags.iter().collect::<Vec<_>>();
+	tags.sort_by(|a, b| a.0.cmp(b.0));
+
+	tags.into_iter().for_each(|(tag, cards)| {
+		let slug = context.tag_slug(tag);
+		let page = format!("tags/{}.md", slug);
+
+		feeds.push(Feed::new(
+			context,
+			format!("{}: {}", title, tag),
+			tag_feed(&context.config, &slug),
+			if context.config.tag_pages { Some(&page) } else { Some("tags.md") },
+			context
+				.articles
+				.iter()
+				.filter(|a| cards.iter().any(|c| c._resolved_path.as_ref() == Some(&a.path)))
+				.collect(),
+		))
+	});
+
+	feeds
+}
+
+/// vygeneruje feedy a zapíše je do zdrojové složky knihy,
+/// viz [`CatConfig::src`]
+///
+/// nedělá nic, pokud nejsou feedy zapnuté
+/// nebo pokud se kniha nesestavuje HTML rendererem
+pub fn write_feeds(context: &CatContext) -> Result<(), CatError> {
+	if !context.config.feed || !context.config.is_html() {
+		return Ok(());
+	}
+
+	if context.config.site_url.is_none() {
+		return Err(CatError::MissingSiteUrl);
+	}
+
+	let mut tt = TinyTemplate::new();
+	tt.set_default_formatter(&format_xml);
+
+	tt.add_template("atom", ATOM_TEMPLATE)
+		.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+	tt.add_template("rss", RSS_TEMPLATE)
+		.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+
+	for feed in create_feeds(context) {
+		let atom =
+			tt.render("atom", &feed).map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		write_if_changed(&context.config.src.join(with_format(&feed.path, "atom")), &atom)?;
+
+		if context.config.feed_rss {
+			let rss = tt
+				.render("rss", &feed)
+				.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+			write_if_changed(&context.config.src.join(with_format(&feed.path, "rss")), &rss)?;
+		}
+	}
+
+	Ok(())
+}
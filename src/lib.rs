@@ -10,11 +10,17 @@
 
 extern crate clap;
 extern crate toml;
+extern crate serde_json;
 extern crate serde;
 extern crate mdbook;
 extern crate walkdir;
 extern crate failure;
 extern crate tinytemplate;
+extern crate unicode_normalization;
+extern crate chrono;
+extern crate pulldown_cmark;
+extern crate toml_edit;
+extern crate caseless;
 
 #[macro_use]
 extern crate shells;
@@ -24,11 +30,23 @@ use mdbook::errors::Error;
 use mdbook::preprocess::{Preprocessor, PreprocessorContext};
 
 pub mod error;
+pub mod config;
+pub mod util;
 pub mod models;
 pub mod render;
+pub mod links;
+pub mod feed;
+pub mod stats;
+pub mod check;
+pub mod export;
+pub mod summary;
+pub mod scaffold;
+pub mod tags;
+pub mod sources;
 pub mod cat_context;
 
 use cat_context::CatContext;
+use config::CatConfig;
 
 /// Samotný preprocesor.
 /// .
@@ -56,14 +74,23 @@ impl Preprocessor for Cat {
 
 	/// spustí preprocesor i s jeho kontextem.
 	///
-	/// `cat-prep` mdbookový kontext nepotřebuje.
+	/// Z mdbookového kontextu `cat-prep` využívá pouze
+	/// svou konfiguraci, viz [`CatConfig`].
 	/// Tato funkce nejdříve vygeneruje [`CatContext`] potřebný
 	/// pro renderování knihy, a posléze ji vyrenderuje.
 	///
 	/// Je nutno dodat, že už i generování kontextu knihu mutuje
 	/// -> dochází k oddělování headerů od obsahu stránky
-	fn run(&self, _: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
-		let context = match CatContext::with_book(&mut book) {
+	fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
+		let config = match CatConfig::from_book_config(&ctx.config, &ctx.renderer) {
+			Ok(c) => c,
+			Err(e) => {
+				eprintln!("[cat prep] failed to read configuration: {}", e);
+				return Err(Error::msg(e.to_string()));
+			}
+		};
+
+		let context = match CatContext::with_book(&mut book, &config) {
 			Ok(c) => c,
 			Err(e) => {
 				eprintln!("[cat prep] failed to create cat context: {}", e);
@@ -84,7 +111,15 @@ impl Preprocessor for Cat {
 			return Err(Error::msg(e.to_string()));
 		}
 
-		dbg!("{:#?}", context);
+		if let Err(e) = feed::write_feeds(&context) {
+			eprintln!("[cat prep] failed to write feeds: {}", e);
+			return Err(Error::msg(e.to_string()));
+		}
+
+		if let Err(e) = export::write_export(&context) {
+			eprintln!("[cat prep] failed to export catalog: {}", e);
+			return Err(Error::msg(e.to_string()));
+		}
 
 		Ok(book)
 	}
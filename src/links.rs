@@ -0,0 +1,188 @@
+//! modul obsahující sestavování odkazů
+//!
+//! Všechny rendery by měly odkazy na jiné kapitoly
+//! vytvářet pomocí [`LinkBuilder`], aby fungovaly
+//! bez ohledu na to, kde je kniha nasazena
+//! a jakým rendererem je sestavena.
+//!
+//! - pokud je nastavena hodnota `site-url`, odkazy jsou absolutní,
+//!   začínají touto adresou a vedou vždy na HTML stránky
+//!   (tedy i z tištěného výstupu nebo EPUBu na web)
+//! - jinak jsou odkazy relativní ke kapitole, ve které se nacházejí,
+//!   takže kniha funguje i v podsložce (např. GitHub Pages projektu)
+//!   nebo mimo webový server
+//!
+//! Relativní odkazy vedou vždy na `.md` soubory, jejich přípony
+//! (i pro `print.html`) přepisuje až renderer. Ostatní renderery
+//! dostávají čistý markdown bez HTML kotev, relativní odkazy
+//! na kotvy proto vedou pouze na kapitolu.
+//!
+//! Části cest jsou v odkazech zakódovány (percent-encoding),
+//! takže odkazy fungují i na soubory s mezerami,
+//! diakritikou nebo znaky jako `#` a `?` v názvu.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::CatConfig;
+
+/// Sestavovač odkazů z jedné kapitoly
+#[derive(Debug, Clone)]
+pub struct LinkBuilder {
+	/// kapitola, ve které se odkazy nacházejí,
+	/// relativní ke složce `src`
+	pub current:   PathBuf,
+	/// adresa webu, pokud je nastavena,
+	/// všechny odkazy jsou absolutní
+	pub site_url:  Option<String>,
+	/// zda je kniha sestavována HTML rendererem
+	pub html:      bool,
+}
+
+impl LinkBuilder {
+	/// vytvoří sestavovač odkazů pro danou kapitolu
+	pub fn new(config: &CatConfig, current: &Path) -> Self {
+		LinkBuilder {
+			current:   current.to_path_buf(),
+			site_url:  config.site_url.clone(),
+			html:      config.is_html(),
+		}
+	}
+
+	/// vytvoří odkaz na danou kapitolu,
+	/// cesta je relativní ke složce `src`
+	pub fn link(&self, target: &Path) -> String {
+		match &self.site_url {
+			Some(url) => format!(
+				"{}/{}",
+				url.trim_end_matches('/'),
+				to_url(&target.with_extension("html"))
+			),
+			None =>
+				to_url(&relative_to(target, self.current.parent().unwrap_or(Path::new("")))),
+		}
+	}
+
+	/// vytvoří odkaz na soubor, který není kapitolou
+	/// (přípona se tedy nemění), cesta je relativní ke složce `src`
+	pub fn file(&self, target: &Path) -> String {
+		match &self.site_url {
+			Some(url) => format!("{}/{}", url.trim_end_matches('/'), to_url(target)),
+			None =>
+				to_url(&relative_to(target, self.current.parent().unwrap_or(Path::new("")))),
+		}
+	}
+
+	/// vytvoří odkaz na výslednou HTML stránku dané kapitoly,
+	/// cesta je relativní ke složce `src`.
+	///
+	/// Takový odkaz renderer nepřepisuje, slouží tedy
+	/// pro výstupy mimo knihu (např. export katalogu)
+	pub fn page(&self, target: &Path) -> String {
+		self.file(&target.with_extension("html"))
+	}
+
+	/// vytvoří odkaz na kotvu v dané kapitole
+	///
+	/// pokud odkaz nevede na HTML stránku, tj. kniha se nesestavuje
+	/// HTML rendererem a není nastaveno `site-url`, kotva se vynechá
+	/// a odkaz vede pouze na kapitolu. Šablony pro ostatní renderery
+	/// totiž nevytvářejí HTML `id`, na které by kotva mohla vést
+	pub fn anchored(&self, target: &Path, anchor: &str) -> String {
+		if self.site_url.is_none() && !self.html {
+			return self.link(target);
+		}
+
+		format!("{}#{}", self.link(target), anchor)
+	}
+}
+
+/// spočítá cestu k `target` relativně ke složce `base`,
+/// obě cesty jsou relativní ke složce `src`
+fn relative_to(target: &Path, base: &Path) -> PathBuf {
+	let target = normalize(target);
+	let base = normalize(base);
+
+	let common = target.iter().zip(base.iter()).take_while(|(a, b)| a == b).count();
+
+	base.iter()
+		.skip(common)
+		.map(|_| "..".to_string())
+		.chain(target.iter().skip(common).cloned())
+		.collect()
+}
+
+/// odstraní z cesty komponenty `.`
+/// a vyřeší komponenty `..`
+fn normalize(path: &Path) -> Vec<String> {
+	let mut res: Vec<String> = vec![];
+
+	for c in path.components() {
+		match c {
+			Component::Normal(x) => res.push(x.to_string_lossy().to_string()),
+			Component::ParentDir => {
+				res.pop();
+			}
+			_ => (),
+		}
+	}
+
+	res
+}
+
+/// převede cestu na část URL (vždy s lomítky),
+/// jednotlivé části cesty jsou zakódovány, viz [`encode`]
+fn to_url(path: &Path) -> String {
+	path.components()
+		.map(|c| encode(&c.as_os_str().to_string_lossy()))
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+/// zakóduje část cesty pro použití v URL (percent-encoding),
+/// beze změny zůstávají pouze písmena a číslice ASCII a znaky `-._~`
+fn encode(segment: &str) -> String {
+	segment
+		.bytes()
+		.map(|b| match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' =>
+				(b as char).to_string(),
+			b => format!("%{:02X}", b),
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn relative_paths_resolve_dot_components() {
+		assert_eq!(relative_to(Path::new("a/b.md"), Path::new("a")), Path::new("b.md"));
+		assert_eq!(
+			relative_to(Path::new("x/y.md"), Path::new("a/b")),
+			Path::new("../../x/y.md")
+		);
+		assert_eq!(
+			relative_to(Path::new("./a/../x/y.md"), Path::new("x/./")),
+			Path::new("y.md")
+		);
+	}
+
+	#[test]
+	fn encodes_reserved_characters() {
+		assert_eq!(encode("čtení #1?.md"), "%C4%8Dten%C3%AD%20%231%3F.md");
+		assert_eq!(encode("a-b_c.~d"), "a-b_c.~d");
+	}
+
+	#[test]
+	fn relative_links_keep_md_extension() {
+		let mut config = CatConfig::default();
+		let links = LinkBuilder::new(&config, Path::new("p/a.md"));
+		assert_eq!(links.link(Path::new("q/b c.md")), "../q/b%20c.md");
+		assert_eq!(links.page(Path::new("q/b c.md")), "../q/b%20c.html");
+
+		config.site_url = Some("https://gjk.cz/wiki/".into());
+		let links = LinkBuilder::new(&config, Path::new("p/a.md"));
+		assert_eq!(links.link(Path::new("q/b c.md")), "https://gjk.cz/wiki/q/b%20c.html");
+	}
+}
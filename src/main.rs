@@ -16,7 +16,10 @@ use clap::{App, Arg, ArgMatches, SubCommand};
 use mdbook::errors::Error;
 use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
 
+use std::fs;
 use std::io;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use cat::Cat;
@@ -30,6 +33,129 @@ pub fn make_app() -> App<'static, 'static> {
 				.arg(Arg::with_name("renderer").required(true))
 				.about("Check whether a renderer is supported by this preprocessor"),
 		)
+		.subcommand(
+			SubCommand::with_name("check")
+				.arg(Arg::with_name("dir").default_value(".").help("Root directory of the book"))
+				.about("Validate a book without building it"),
+		)
+		.subcommand(
+			SubCommand::with_name("new")
+				.about("Create a new teacher, subject or article")
+				.arg(
+					Arg::with_name("book")
+						.long("book")
+						.takes_value(true)
+						.default_value(".")
+						.global(true)
+						.help("Root directory of the book"),
+				)
+				.subcommand(
+					SubCommand::with_name("teacher")
+						.about("Create a teacher card, name and email default to git config")
+						.arg(Arg::with_name("username").long("username").takes_value(true))
+						.arg(Arg::with_name("name").long("name").takes_value(true))
+						.arg(Arg::with_name("email").long("email").takes_value(true)),
+				)
+				.subcommand(
+					SubCommand::with_name("subject")
+						.about("Create a subject and add it to SUMMARY.md")
+						.arg(
+							Arg::with_name("dir")
+								.required(true)
+								.help("Directory of the subject, relative to src"),
+						)
+						.arg(Arg::with_name("name").long("name").takes_value(true)),
+				)
+				.subcommand(
+					SubCommand::with_name("article")
+						.about("Create an article and add it to SUMMARY.md under its subject")
+						.arg(
+							Arg::with_name("subject")
+								.required(true)
+								.help("Directory (relative to src) or name of the subject"),
+						)
+						.arg(Arg::with_name("name").required(true).help("Name of the article")),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("export")
+				.arg(Arg::with_name("dir").default_value(".").help("Root directory of the book"))
+				.arg(
+					Arg::with_name("output")
+						.short("o")
+						.long("output")
+						.takes_value(true)
+						.help("Output file, defaults to standard output"),
+				)
+				.about("Export the catalog of teachers, subjects, articles and tags as JSON"),
+		)
+		.subcommand(
+			SubCommand::with_name("stats")
+				.arg(Arg::with_name("dir").default_value(".").help("Root directory of the book"))
+				.arg(
+					Arg::with_name("format")
+						.long("format")
+						.takes_value(true)
+						.possible_values(&["table", "markdown", "json"])
+						.default_value("table"),
+				)
+				.about("Summarize contributions of teachers and coverage of subjects"),
+		)
+		.subcommand(
+			SubCommand::with_name("tags")
+				.about("Rename or merge tags in all article headers")
+				.arg(
+					Arg::with_name("book")
+						.long("book")
+						.takes_value(true)
+						.default_value(".")
+						.global(true)
+						.help("Root directory of the book"),
+				)
+				.arg(
+					Arg::with_name("dry-run")
+						.long("dry-run")
+						.global(true)
+						.help("Only print the changes as a diff"),
+				)
+				.subcommand(
+					SubCommand::with_name("rename")
+						.about("Rename a tag")
+						.arg(Arg::with_name("old").required(true))
+						.arg(Arg::with_name("new").required(true)),
+				)
+				.subcommand(
+					SubCommand::with_name("merge")
+						.about("Merge tags into one")
+						.arg(Arg::with_name("into").required(true).help("Resulting tag"))
+						.arg(
+							Arg::with_name("tags")
+								.required(true)
+								.multiple(true)
+								.help("Tags merged into the resulting tag"),
+						),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("summary")
+				.about("Work with SUMMARY.md")
+				.subcommand(
+					SubCommand::with_name("sync")
+						.about("Add subjects and articles missing from SUMMARY.md")
+						.arg(
+							Arg::with_name("book")
+								.long("book")
+								.takes_value(true)
+								.default_value(".")
+								.help("Root directory of the book"),
+						)
+						.arg(
+							Arg::with_name("dry-run")
+								.long("dry-run")
+								.help("Only print the missing chapters"),
+						),
+				),
+		)
 }
 
 fn main() {
@@ -39,6 +165,18 @@ fn main() {
 
 	if let Some(sub_args) = matches.subcommand_matches("supports") {
 		handle_supports(&preprocessor, sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("check") {
+		handle_check(sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("new") {
+		handle_new(sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("summary") {
+		handle_summary(sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("tags") {
+		handle_tags(sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("export") {
+		handle_export(sub_args);
+	} else if let Some(sub_args) = matches.subcommand_matches("stats") {
+		handle_stats(sub_args);
 	} else if let Err(e) = handle_preprocessing(&preprocessor) {
 		eprintln!("[cat prep] {}", e);
 		process::exit(1);
@@ -74,3 +212,179 @@ fn handle_supports(pre: &dyn Preprocessor, sub_args: &ArgMatches) -> ! {
 		process::exit(1);
 	}
 }
+
+fn handle_check(sub_args: &ArgMatches) -> ! {
+	let dir = sub_args.value_of("dir").expect("Has default value");
+
+	match cat::check::check(Path::new(dir)) {
+		Ok(r) => {
+			eprintln!(
+				"[cat prep] check passed: {} teachers, {} subjects, {} articles, {} tags",
+				r.teachers, r.subjects, r.articles, r.tags
+			);
+			process::exit(0);
+		}
+		Err(e) => {
+			eprintln!("[cat prep] check failed: {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+fn handle_new(sub_args: &ArgMatches) -> ! {
+	let res = match sub_args.subcommand() {
+		("teacher", Some(args)) => cat::scaffold::new_teacher(
+			Path::new(args.value_of("book").expect("Has default value")),
+			args.value_of("username"),
+			args.value_of("name"),
+			args.value_of("email"),
+		),
+		("subject", Some(args)) => cat::scaffold::new_subject(
+			Path::new(args.value_of("book").expect("Has default value")),
+			Path::new(args.value_of("dir").expect("Required argument")),
+			args.value_of("name"),
+		),
+		("article", Some(args)) => cat::scaffold::new_article(
+			Path::new(args.value_of("book").expect("Has default value")),
+			args.value_of("subject").expect("Required argument"),
+			args.value_of("name").expect("Required argument"),
+		),
+		_ => {
+			eprintln!("{}", sub_args.usage());
+			process::exit(1);
+		}
+	};
+
+	match res {
+		Ok(path) => {
+			eprintln!("[cat prep] created {}", path.display());
+			process::exit(0);
+		}
+		Err(e) => {
+			eprintln!("[cat prep] {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+fn handle_summary(sub_args: &ArgMatches) -> ! {
+	let args = match sub_args.subcommand() {
+		("sync", Some(args)) => args,
+		_ => {
+			eprintln!("{}", sub_args.usage());
+			process::exit(1);
+		}
+	};
+
+	let dry_run = args.is_present("dry-run");
+
+	match cat::summary::sync(Path::new(args.value_of("book").expect("Has default value")), dry_run)
+	{
+		Ok(added) => {
+			added.iter().for_each(|x| {
+				eprintln!(
+					"[cat prep] {} {} ({})",
+					if dry_run { "missing" } else { "added" },
+					x.path.display(),
+					x.nazev
+				)
+			});
+			process::exit(0);
+		}
+		Err(e) => {
+			eprintln!("[cat prep] {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+fn handle_export(sub_args: &ArgMatches) -> ! {
+	let dir = sub_args.value_of("dir").expect("Has default value");
+	// načtení knihy mění pracovní adresář
+	let output = sub_args
+		.value_of("output")
+		.map(|o| env::current_dir().map(|d| d.join(o)).unwrap_or_else(|_| PathBuf::from(o)));
+
+	let json = cat::check::load(Path::new(dir))
+		.and_then(|(_, context)| cat::export::Catalog::new(&context).to_json());
+
+	let res = match (json, output) {
+		(Ok(json), Some(output)) => fs::write(output, json).map_err(|e| e.to_string()),
+		(Ok(json), None) => {
+			println!("{}", json);
+			Ok(())
+		}
+		(Err(e), _) => Err(e.to_string()),
+	};
+
+	if let Err(e) = res {
+		eprintln!("[cat prep] export failed: {}", e);
+		process::exit(1);
+	}
+
+	process::exit(0);
+}
+
+fn handle_stats(sub_args: &ArgMatches) -> ! {
+	let dir = sub_args.value_of("dir").expect("Has default value");
+
+	let report = cat::check::load(Path::new(dir))
+		.map(|(_, context)| cat::stats::Report::new(&context))
+		.and_then(|r| match sub_args.value_of("format") {
+			Some("json") => r.to_json(),
+			Some("markdown") => Ok(r.to_text(true)),
+			_ => Ok(r.to_text(false)),
+		});
+
+	match report {
+		Ok(r) => {
+			println!("{}", r);
+			process::exit(0);
+		}
+		Err(e) => {
+			eprintln!("[cat prep] stats failed: {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+fn handle_tags(sub_args: &ArgMatches) -> ! {
+	let (args, sources, target) = match sub_args.subcommand() {
+		("rename", Some(args)) => (
+			args,
+			vec![args.value_of("old").expect("Required argument")],
+			args.value_of("new").expect("Required argument"),
+		),
+		("merge", Some(args)) => (
+			args,
+			args.values_of("tags").expect("Required argument").collect(),
+			args.value_of("into").expect("Required argument"),
+		),
+		_ => {
+			eprintln!("{}", sub_args.usage());
+			process::exit(1);
+		}
+	};
+
+	let dry_run = args.is_present("dry-run");
+	let book = Path::new(args.value_of("book").expect("Has default value"));
+
+	match cat::tags::retag(book, &sources, target, dry_run) {
+		Ok(edits) => {
+			if dry_run {
+				edits.iter().for_each(|e| print!("{}", e.diff()));
+			}
+
+			eprintln!(
+				"[cat prep] {} {} files",
+				if dry_run { "would change" } else { "changed" },
+				edits.len()
+			);
+			process::exit(0);
+		}
+		Err(e) => {
+			eprintln!("[cat prep] {}", e);
+			process::exit(1);
+		}
+	}
+}
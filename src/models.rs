@@ -4,8 +4,9 @@
 //! kdy karta odpovídá TOMLu přečtenému z headeru
 //! souboru (nebo ze souboru ucitel.toml)
 
-use std::path::PathBuf;
-use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize, Deserializer};
 
 /// Karta učitele
 ///
@@ -41,6 +42,108 @@ pub struct TeacherCard {
 	pub bio:      String,
 }
 
+impl TeacherCard {
+	/// zda daný řetězec (např. `zodpovedna_osoba` nebo autor
+	/// commitu) odpovídá jménu, emailu nebo usernamu vyučujícího
+	pub fn matches(&self, name: &str) -> bool {
+		name == self.username || name == self.jmeno || name == self.email
+	}
+}
+
+/// Karta tagu
+///
+/// Tato struktura reprezentuje jednu tabulku souboru
+/// `tags.toml`, název tabulky je kanonický tvar tagu,
+/// pod kterým se tag zobrazuje
+///
+/// ```toml
+/// ["Python"]
+/// aliasy = ["python3", "py"]
+/// popis = "Programovací jazyk Python"
+/// barva = "#3572a5"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagCard {
+	/// další zápisy tagu, které se sloučí s tímto tagem
+	pub aliasy: Vec<String>,
+	/// popisek tagu, formátován jako markdown
+	pub popis:  Option<String>,
+	/// barva tagu v oblaku tagů (HTML renderer),
+	/// libovolná CSS barva
+	pub barva:  Option<String>,
+}
+
+/// Uzel stromu hierarchických tagů
+///
+/// hierarchické tagy se zapisují jako cesta oddělená `/`,
+/// např. `programování/rust/ownership`. Článek s takovým tagem
+/// patří i pod všechny jeho předky
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagNode {
+	/// poslední část cesty tagu
+	pub name:     String,
+	/// celá cesta tagu, slouží jako jeho identifikátor
+	pub path:     String,
+	/// počet článků s tímto tagem nebo některým z jeho potomků
+	pub count:    usize,
+	/// přímí potomci tagu
+	pub children: Vec<TagNode>,
+}
+
+/// Skupina tagů ve slovníku tagů
+///
+/// Slovník (soubor nastavený v `tag-vocabulary`) je sám skupinou,
+/// tagy mimo skupiny jsou v jeho poli `tagy`, ostatní
+/// tabulky jsou podskupiny, které mohou být libovolně zanořené
+///
+/// ```toml
+/// tagy = ["základy"]
+///
+/// ["programování"]
+/// tagy = ["Python", "C#"]
+///
+/// ["programování".web]
+/// tagy = ["HTML", "CSS"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagGroup {
+	/// tagy přímo v této skupině
+	#[serde(default)]
+	pub tagy:    Vec<String>,
+	/// podskupiny podle názvu
+	#[serde(flatten)]
+	pub skupiny: BTreeMap<String, TagGroup>,
+}
+
+impl TagGroup {
+	/// všechny tagy skupiny včetně podskupin
+	pub fn all_tags(&self) -> Vec<String> {
+		let mut res = self.tagy.clone();
+		self.skupiny.values().for_each(|x| res.extend(x.all_tags()));
+
+		res
+	}
+
+	/// zploští podskupiny na seznam dvojic cesta - tagy,
+	/// cesta je tvořena názvy skupin oddělenými `" › "`
+	pub fn categories(&self) -> Vec<(String, Vec<String>)> {
+		let mut res = vec![];
+
+		self.skupiny.iter().for_each(|(name, group)| {
+			res.push((name.clone(), group.tagy.clone()));
+			res.extend(
+				group
+					.categories()
+					.into_iter()
+					.map(|(path, tags)| (format!("{} › {}", name, path), tags)),
+			);
+		});
+
+		res
+	}
+}
+
 /// Karta článku
 ///
 /// Tato struktura reprezentuje konfigurační
@@ -61,6 +164,24 @@ pub struct ArticleCard {
 	pub tagy:           Vec<String>,
 	/// datum, může obsahovat cokoliv
 	pub datum:          Option<String>,
+	/// pořadí článku v předmětu,
+	/// využito, pokud předmět řadí články podle [`Razeni::Poradi`]
+	pub poradi:         Option<i64>,
+	/// počet dní od poslední změny, po kterých je článek
+	/// považován za zastaralý, přebíjí globální `max-stari`
+	pub revidovat_po:   Option<u64>,
+	/// datum, do kterého má být článek zrevidován,
+	/// ve stejném formátu jako `datum` (viz [`crate::cat_context::parse_datum`])
+	pub revidovat_do:   Option<String>,
+	/// předměty, do kterých článek patří kromě předmětu
+	/// ve své složce, viz [`Subject::matches`].
+	/// V headeru lze zapsat jako řetězec i seznam
+	#[serde(default, deserialize_with = "one_or_many")]
+	pub predmet:        Vec<String>,
+	/// článek mimo předměty, který nepatří do žádného předmětu,
+	/// viz též `standalone` v [`crate::config::CatConfig`]
+	#[serde(default)]
+	pub samostatny:     bool,
 	/// tato složka je pomocná a nemá být
 	/// konfigurována v markdown souboru,
 	/// jejím účelem je uchovávat cestu k souboru,
@@ -70,6 +191,24 @@ pub struct ArticleCard {
 	pub _resolved_path: Option<PathBuf>,
 }
 
+/// přečte hodnotu, která může být jeden řetězec i seznam řetězců
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum OneOrMany {
+		One(String),
+		Many(Vec<String>),
+	}
+
+	Ok(match OneOrMany::deserialize(deserializer)? {
+		OneOrMany::One(x) => vec![x],
+		OneOrMany::Many(x) => x,
+	})
+}
+
 /// Článek
 ///
 /// Tato struktura reprezentuje všechna
@@ -78,31 +217,91 @@ pub struct ArticleCard {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Article {
 	/// karta článku
-	pub card:              ArticleCard,
+	pub card:               ArticleCard,
 	/// kdy byl naposled článek modifikován;
 	/// vypočítáno pomocí gitu.
-	pub last_modified:     String,
+	pub last_modified:      String,
+	/// kdy byl naposled článek modifikován,
+	/// jako unixový timestamp; využito pro řazení.
+	/// `None`, pokud článek nemá historii (ještě není v gitu)
+	pub modified_timestamp: Option<i64>,
+	/// kdy byl článek vytvořen (přidán do gitu),
+	/// jako unixový timestamp; `None`, pokud článek nemá historii
+	pub created_timestamp:  Option<i64>,
 	/// kým byl článek naposledy modifikován;
 	/// informaca získané z gitu.
-	pub modified_by:       String,
+	pub modified_by:        String,
 	/// autor daného materiálu,
 	/// informace získaná z gitu,
 	/// viz [`Teacher::files_created`].
-	pub author:            String,
+	pub author:             String,
 	/// cesta k článku,
 	/// relativní ke složce `src`
 	/// (a tudíž kořenovému adresáři webu)
-	pub path:              PathBuf,
+	pub path:               PathBuf,
 	/// pokud se podle [`Article::modified_by`] podařilo
 	/// najít vyučujícího, bude zde uložena jeho karta
-	pub modified_resolved: Option<TeacherCard>,
+	pub modified_resolved:  Option<TeacherCard>,
 	/// pokud se podle [`Article::author`] podařilo
 	/// najít vyučujícího, bude zde uložena jeho karta
-	pub resolved_author:   Option<TeacherCard>,
+	pub resolved_author:    Option<TeacherCard>,
 	/// zde se nalézá přiřazená karta předmětu,
 	/// typ `Option` je použit proto, protože v době
-	/// parsování není známý předmět, ke kterému článek patří
-	pub subject_card:      Option<SubjectCard>,
+	/// parsování není známý předmět, ke kterému článek patří.
+	///
+	/// Patří-li článek do více předmětů, jedná se o hlavní předmět
+	/// (předmět ve složce článku, jinak první z `predmet`)
+	pub subject_card:       Option<SubjectCard>,
+	/// karty všech předmětů, do kterých článek patří,
+	/// hlavní předmět je první
+	pub subject_cards:      Vec<SubjectCard>,
+	/// statistiky obsahu článku, viz [`crate::stats`]
+	pub stats:              ArticleStats,
+	/// článek může být zastaralý, viz [`crate::cat_context::is_stale`]
+	pub stale:              bool,
+}
+
+/// Statistiky obsahu materiálu
+///
+/// U předmětu obsahují součet statistik všech jeho materiálů
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArticleStats {
+	/// počet slov (bez bloků kódu)
+	pub words:           usize,
+	/// odhadovaná doba čtení v minutách
+	pub reading_minutes: usize,
+	/// počet bloků kódu
+	pub code_blocks:     usize,
+	/// počet obrázků
+	pub images:          usize,
+	/// počet nadpisů
+	pub headings:        usize,
+}
+
+/// Řazení článků v předmětu
+///
+/// V headeru předmětu se zadává jako `razeni = "summary"` apod.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Razeni {
+	/// podle pořadí v `SUMMARY.md`
+	Summary,
+	/// abecedně podle názvu (výchozí)
+	Nazev,
+	/// chronologicky podle hodnoty `datum`,
+	/// pokud ji lze přečíst jako datum
+	Datum,
+	/// od naposledy změněného článku
+	LastModified,
+	/// podle hodnoty `poradi` v kartě článku,
+	/// články bez pořadí následují abecedně
+	Poradi,
+}
+
+impl Default for Razeni {
+	fn default() -> Self {
+		Razeni::Nazev
+	}
 }
 
 /// Karta předmětu
@@ -124,6 +323,9 @@ pub struct SubjectCard {
 	pub zodpovedna_osoba: String,
 	/// krátký popisek předmětu
 	pub bio:              String,
+	/// řazení článků v předmětu, viz [`Razeni`]
+	#[serde(default)]
+	pub razeni:           Razeni,
 	/// cesta k předmětu,
 	/// pro účely, kde je dostupná jenom karta
 	/// předmětu nebo dokud není vytvořený profil
@@ -154,6 +356,61 @@ pub struct Subject {
 	/// zodpovědné osoby, zde je uložena
 	/// její karta
 	pub resolved_author: Option<TeacherCard>,
+	/// všichni vyučující, kteří se na předmětu podílejí,
+	/// seřazení podle své nejvýznamnější role
+	pub teachers:        Vec<SubjectTeacher>,
+	/// součet statistik všech článků předmětu
+	pub stats:           ArticleStats,
+}
+
+impl Subject {
+	/// zda daná hodnota (např. `predmet` článku) odpovídá názvu předmětu
+	/// (bez ohledu na velikost písmen), jeho složce nebo cestě k `subject.md`,
+	/// cesty jsou relativní ke složce `src`
+	pub fn matches(&self, value: &str) -> bool {
+		let value = value.trim();
+		let path = Path::new(value.trim_start_matches("./").trim_end_matches('/'));
+
+		value.to_lowercase() == self.card.nazev.to_lowercase()
+			|| path == self.path
+			|| path == self.path_root
+	}
+}
+
+/// role vyučujícího v předmětu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+	/// zodpovědná osoba předmětu, viz [`SubjectCard::zodpovedna_osoba`]
+	Responsible,
+	/// založil předmět nebo některý z jeho materiálů
+	Author,
+	/// upravoval předmět nebo jeho materiály,
+	/// ale žádný z nich nezaložil
+	Contributor,
+}
+
+/// vazba mezi vyučujícím a předmětem
+///
+/// vyučující může mít v jednom předmětu více rolí,
+/// každá role je samostatnou vazbou
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubjectRole {
+	/// username vyučujícího
+	pub username: String,
+	/// cesta k předmětu
+	pub subject:  PathBuf,
+	/// role vyučujícího v předmětu
+	pub role:     Role,
+}
+
+/// vyučující podílející se na předmětu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectTeacher {
+	/// karta vyučujícího
+	pub card:  TeacherCard,
+	/// role vyučujícího v předmětu
+	pub roles: Vec<Role>,
 }
 
 /// Učitel
@@ -163,13 +420,28 @@ pub struct Subject {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Teacher {
 	/// karta učitele
-	pub card:          TeacherCard,
+	pub card:           TeacherCard,
 	/// předměty, na kterých se
-	/// vyučující podílel
-	pub subjects:      Vec<Subject>,
+	/// vyučující podílel v jakékoliv roli
+	pub subjects:       Vec<Subject>,
+	/// role vyučujícího v předmětech z `subjects`
+	pub roles:          Vec<SubjectRole>,
 	/// články, které vyučující založil
-	pub articles:      Vec<Article>,
+	pub articles:       Vec<Article>,
 	/// seznam souborů, které uživatel přidal
 	/// do gitu a stále existují
-	pub files_created: Vec<PathBuf>,
+	pub files_created:  Vec<PathBuf>,
+	/// seznam souborů, které uživatel upravil
+	/// (včetně založení) a stále existují
+	pub files_modified: Vec<PathBuf>,
+}
+
+impl Teacher {
+	/// předměty, ve kterých má vyučující danou roli
+	pub fn subjects_with(&self, role: Role) -> Vec<&Subject> {
+		self.subjects
+			.iter()
+			.filter(|s| self.roles.iter().any(|r| r.subject == s.path && r.role == role))
+			.collect()
+	}
 }
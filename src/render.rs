@@ -18,9 +18,13 @@
 //!
 //! V budoucnu by bylo možné využít makra `include_str!()`
 //! k extrakci těchto šablon do vnějších souborů.
+//!
+//! Šablony, které obsahují HTML nebo skripty, mají variantu
+//! s příponou `_PRINT` v čistém markdownu, která se použije
+//! pro všechny renderery kromě HTML, viz [`renderer_template`].
 
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::convert::From;
 use std::collections::HashMap;
 
@@ -30,10 +34,17 @@ use mdbook::{
 };
 use tinytemplate::TinyTemplate;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
 
 use crate::cat_context::CatContext;
+use crate::config::Placement;
 use crate::error::CatError;
+use crate::feed;
+use crate::links::LinkBuilder;
 use crate::models::*;
+use crate::tags::{find_tag_card, normalize_tag, parent_tag, tag_prefixes};
 
 /// typ daného renderu (a jeho obsah).
 /// Určuje chování, jakým bude zacházeno
@@ -86,6 +97,213 @@ impl RenderSite {
 	}
 }
 
+/// vytvoří z daného stringu slug,
+/// použitelný jako HTML id nebo název souboru.
+///
+/// Diakritika je odstraněna, písmena jsou převedena na malá
+/// a vše ostatní kromě číslic je nahrazeno pomlčkou.
+/// Znaky `#` a `+` jsou přepsány slovy, aby se od sebe
+/// odlišily například tagy `C`, `C#` a `C++`.
+pub fn slugify(src: &str) -> String {
+	let mut words: Vec<String> = vec![];
+	let mut word = String::new();
+
+	for c in src.nfd().filter(|c| !is_combining_mark(*c)) {
+		match c {
+			c if c.is_alphanumeric() => word.extend(c.to_lowercase()),
+			c => {
+				words.push(word.clone());
+				word.clear();
+
+				match c {
+					'#' => words.push("sharp".into()),
+					'+' => words.push("plus".into()),
+					_ => (),
+				}
+			}
+		}
+	}
+	words.push(word);
+
+	let mut slug =
+		words.into_iter().filter(|x| !x.is_empty()).collect::<Vec<_>>().join("-");
+
+	if slug.is_empty() {
+		// string neobsahuje nic použitelného,
+		// slug se tedy sestaví z kódů jednotlivých znaků
+		slug = src.chars().map(|c| format!("{:x}", c as u32)).collect::<Vec<_>>().join("-");
+	}
+
+	slug
+}
+
+/// přidělí tagům unikátní slugy, viz [`slugify`]
+///
+/// tagy jsou procházeny abecedně, pokud má více tagů stejný slug
+/// (např. `C#` a `c sharp`), první z nich jej dostane beze změny
+/// a ostatní s příponou `-2`, `-3`, ...
+pub fn tag_slugs<'a>(tags: impl IntoIterator<Item = &'a String>) -> HashMap<String, String> {
+	let mut names = tags.into_iter().collect::<Vec<_>>();
+	names.sort();
+	names.dedup();
+
+	let slugs = names.iter().map(|x| slugify(x)).collect::<Vec<_>>();
+	let mut taken: Vec<String> = vec![];
+	let mut res = HashMap::new();
+
+	for (name, slug) in names.iter().zip(&slugs) {
+		let unique = if taken.contains(slug) {
+			(2..)
+				.map(|i| format!("{}-{}", slug, i))
+				.find(|x| !taken.contains(x) && !slugs.contains(x))
+				.unwrap()
+		} else {
+			slug.clone()
+		};
+
+		taken.push(unique.clone());
+		res.insert(name.to_string(), unique);
+	}
+
+	res
+}
+
+/// vybere šablonu podle rendereru, pro který je kniha sestavována
+pub fn renderer_template(
+	context: &CatContext,
+	html: &'static str,
+	print: &'static str,
+) -> &'static str {
+	if context.config.is_html() {
+		html
+	} else {
+		print
+	}
+}
+
+/// vytvoří odkaz na Atom feed s danou cestou (bez přípony),
+/// pokud jsou feedy zapnuté a kniha se sestavuje HTML rendererem
+pub fn feed_link(context: &CatContext, site: &Path, feed: &Path) -> String {
+	if !context.config.feed || !context.config.is_html() {
+		return "".into();
+	}
+
+	format!(
+		"\n[Odebírat změny (Atom)]({})\n",
+		LinkBuilder::new(&context.config, site).file(&feed::with_format(feed, "atom"))
+	)
+}
+
+/// přidělí vyučujícím unikátní slugy usernamů stejně jako [`tag_slugs`],
+/// aby dva usernamy se stejným slugem nesdílely stránku ani kotvu
+pub fn teacher_slugs<'a>(
+	usernames: impl IntoIterator<Item = &'a String>,
+) -> HashMap<String, String> {
+	tag_slugs(usernames)
+}
+
+/// cesta k vlastní stránce vyučujícího s daným slugem
+///
+/// název souboru je slug usernamu (viz [`CatContext::teacher_slug`]),
+/// aby username obsahující například tečku nebo lomítko nevytvořil jinou cestu
+pub fn teacher_page(slug: &str) -> PathBuf {
+	PathBuf::from(format!("teachers/{}.md", slug))
+}
+
+/// zjistí, zda je daná cesta stránkou, kterou `cat-prep` generuje,
+/// tj. zda ji v `SUMMARY.md` může zastupovat kapitola bez souboru
+pub fn is_generated_page(path: &Path) -> bool {
+	let pages = ["subjects.md", "teachers.md", "recent.md", "maintenance.md", "tags.md"];
+
+	pages.iter().any(|p| path == Path::new(p))
+		|| path.parent() == Some(Path::new("teachers"))
+		|| path.parent() == Some(Path::new("tags"))
+}
+
+/// vytvoří `TinyTemplate` se všemi formátovači,
+/// které využívají šablony `cat-prepu`.
+///
+/// `site` je kapitola, do které bude výsledek šablony vložen,
+/// odkazy jsou sestavovány pomocí [`LinkBuilder`] vůči ní.
+///
+/// - `slug` - převede hodnotu pomocí [`slugify`]
+/// - `link` - vytvoří odkaz na kapitolu s danou cestou
+/// - `teacher_link` - vytvoří odkaz na vyučujícího s daným usernamem,
+///   buď na jeho vlastní stránku, nebo na kotvu na stránce `teachers.md`,
+///   viz [`CatContext::teacher_slug`]
+/// - `tag_link` - vytvoří odkaz na daný tag, buď na jeho
+///   vlastní stránku, nebo na kotvu na stránce `tags.md`,
+///   viz [`CatContext::tag_slug`]
+/// - `role` - převede [`Role`] na český název role
+pub fn template_engine(context: &CatContext, site: &Path) -> TinyTemplate<'static> {
+	let mut tt = TinyTemplate::new();
+	let links = LinkBuilder::new(&context.config, site);
+
+	tt.add_formatter("slug", |v, out| match v {
+		Value::String(s) => {
+			out.push_str(&slugify(s));
+			Ok(())
+		}
+		_ => tinytemplate::format(v, out),
+	});
+
+	let l = links.clone();
+	tt.add_formatter("link", move |v, out| match v {
+		Value::String(s) => {
+			out.push_str(&l.link(Path::new(s)));
+			Ok(())
+		}
+		_ => tinytemplate::format(v, out),
+	});
+
+	let l = links.clone();
+	let teacher_pages = context.config.teacher_pages;
+	let slugs = context.teacher_slugs.clone();
+	let teacher_slug = move |s: &str| slugs.get(s).cloned().unwrap_or_else(|| slugify(s));
+	tt.add_formatter("teacher_link", move |v, out| match v {
+		Value::String(s) if teacher_pages => {
+			out.push_str(&l.link(&teacher_page(&teacher_slug(s))));
+			Ok(())
+		}
+		Value::String(s) => {
+			out.push_str(&l.anchored(Path::new("teachers.md"), &teacher_slug(s)));
+			Ok(())
+		}
+		_ => tinytemplate::format(v, out),
+	});
+
+	let l = links;
+	let tag_pages = context.config.tag_pages;
+	let slugs = context.tag_slugs.clone();
+	let slug = move |s: &str| slugs.get(s).cloned().unwrap_or_else(|| slugify(s));
+	tt.add_formatter("tag_link", move |v, out| match v {
+		Value::String(s) if tag_pages => {
+			out.push_str(&l.link(&PathBuf::from(format!("tags/{}.md", slug(s)))));
+			Ok(())
+		}
+		Value::String(s) => {
+			out.push_str(&l.anchored(Path::new("tags.md"), &slug(s)));
+			Ok(())
+		}
+		_ => tinytemplate::format(v, out),
+	});
+
+	tt.add_formatter("role", |v, out| match v {
+		Value::String(s) => {
+			out.push_str(match s.as_str() {
+				"responsible" => "zodpovědná osoba",
+				"author" => "autor",
+				"contributor" => "přispěvatel",
+				x => x,
+			});
+			Ok(())
+		}
+		_ => tinytemplate::format(v, out),
+	});
+
+	tt
+}
+
 /// Trait umožňující renderování struktury
 /// jako Markdown/HT?ML
 pub trait Render {
@@ -98,59 +316,161 @@ pub trait Render {
 
 /// šablona karty učitele
 pub static TEACHER_TEMPLATE: &'static str = r#"
-<h2 id="{card.username}">{card.jmeno}</h2>
+<h2 id="{slug}">{teacher.card.jmeno}</h2>
+{{ call teacher_body with teacher }}<hr>
+"#;
+
+/// šablona karty učitele pro renderery kromě HTML
+pub static TEACHER_PRINT_TEMPLATE: &'static str = r#"
+## {teacher.card.jmeno}
+{{ call teacher_body with teacher }}"#;
+
+/// šablona vlastní stránky učitele
+pub static TEACHER_PAGE_TEMPLATE: &'static str = r#"# {teacher.card.jmeno}
+{{ call teacher_body with teacher }}"#;
 
-- email: <a href="mailto:{card.email}">{card.email}</a>
+/// společný obsah karty i vlastní stránky učitele,
+/// volaný z ostatních šablon učitele s [`Teacher`] jako kontextem
+pub static TEACHER_BODY_TEMPLATE: &'static str = r#"
+- email: {{ call teacher_email with card }}
 - username: {card.username}
 
 ### Bio
 {card.bio}
 
 ### Předměty
-{{ for p in subjects }} - [{p.card.nazev}](/{p.path})
-{{ endfor }}
+{{ if responsible_for }}
+#### Zodpovídá za
+{{ call chapter_list with responsible_for }}{{ endif }}{{ if authored }}
+#### Autor
+{{ call chapter_list with authored }}{{ endif }}{{ if contributed }}
+#### Přispěvatel
+{{ call chapter_list with contributed }}{{ endif }}
 
 ### Materiály
-{{ for a in articles }} - [{a.card.nazev}](/{a.path})
-{{ endfor }}
-<hr>
+{{ call chapter_list with articles }}
 "#;
 
-impl Render for Teacher {
-	fn render(&self, _: &CatContext) -> Result<RenderSite, CatError> {
-		let render_site = PathBuf::from("teachers.md");
-		let mut tt = TinyTemplate::new();
+/// seznam odkazů na předměty nebo články,
+/// volaný se seznamem [`Subject`] nebo [`Article`] jako kontextem
+pub static CHAPTER_LIST_TEMPLATE: &'static str = r#"{{ for x in @root }} - [{x.card.nazev}]({x.path | link})
+{{ endfor }}"#;
+
+/// email učitele, volaný s [`TeacherCard`] jako kontextem
+pub static TEACHER_EMAIL_TEMPLATE: &'static str = r#"<a href="mailto:{email}">{email}</a>"#;
+
+/// email učitele pro renderery kromě HTML
+pub static TEACHER_EMAIL_PRINT_TEMPLATE: &'static str = r#"{email}"#;
+
+/// tato struktura existuje jako způsob obcházení limitací `tinytemplate`,
+/// který neumí předat kořenový kontext do volané šablony
+#[derive(Debug, Serialize)]
+pub struct TeacherView<'a> {
+	/// renderovaný učitel
+	pub teacher: TeacherSubjects<'a>,
+	/// slug učitele, viz [`CatContext::teacher_slug`]
+	pub slug:    String,
+}
+
+/// učitel s předměty rozdělenými podle jeho rolí,
+/// viz [`Teacher::subjects_with`]
+#[derive(Debug, Serialize)]
+pub struct TeacherSubjects<'a> {
+	/// renderovaný učitel
+	#[serde(flatten)]
+	pub teacher:         &'a Teacher,
+	/// předměty, za které vyučující zodpovídá
+	pub responsible_for: Vec<&'a Subject>,
+	/// předměty, ve kterých vyučující založil
+	/// předmět nebo některý materiál
+	pub authored:        Vec<&'a Subject>,
+	/// předměty, do kterých vyučující přispěl úpravami
+	pub contributed:     Vec<&'a Subject>,
+}
 
-		tt.add_template("teacher", TEACHER_TEMPLATE)
+impl<'a> From<&'a Teacher> for TeacherSubjects<'a> {
+	fn from(teacher: &'a Teacher) -> Self {
+		TeacherSubjects {
+			teacher,
+			responsible_for: teacher.subjects_with(Role::Responsible),
+			authored: teacher.subjects_with(Role::Author),
+			contributed: teacher.subjects_with(Role::Contributor),
+		}
+	}
+}
+
+impl Render for Teacher {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
+		let pages = context.config.teacher_pages;
+		let slug = context.teacher_slug(&self.card.username);
+		let (render_site, template) = if pages {
+			(teacher_page(&slug), TEACHER_PAGE_TEMPLATE)
+		} else {
+			(
+				PathBuf::from("teachers.md"),
+				renderer_template(context, TEACHER_TEMPLATE, TEACHER_PRINT_TEMPLATE),
+			)
+		};
+		let mut tt = template_engine(context, &render_site);
+
+		tt.add_template("teacher", template)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("teacher_body", TEACHER_BODY_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("chapter_list", CHAPTER_LIST_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template(
+			"teacher_email",
+			renderer_template(context, TEACHER_EMAIL_TEMPLATE, TEACHER_EMAIL_PRINT_TEMPLATE),
+		)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 		let res = tt
-			.render("teacher", &self)
+			.render("teacher", &TeacherView { teacher: self.into(), slug })
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
 		dbg!("{}", &res);
 
-		Ok(RenderSite::new(render_site, Append(res)))
+		if pages {
+			Ok(RenderSite::new(render_site, EntirePage(res)))
+		} else {
+			Ok(RenderSite::new(render_site, Append(res)))
+		}
 	}
 }
 
 /// šablona pro seznam učitelů
+///
+/// pokud mají učitelé vlastní stránky, vypisuje se
+/// seznam s emaily, jinak pouze odkazy na kotvy
 pub static TEACHER_LIST_TEMPLATE: &'static str = r#"
-{{ for t in list }} [{t.jmeno}](#{t.username}) {{ endfor }}
+{{ if pages }}{{ for t in list }}- [{t.jmeno}]({t.username | teacher_link}) - <a href="mailto:{t.email}">{t.email}</a>
+{{ endfor }}{{ else }}{{ for t in list }} [{t.jmeno}]({t.username | teacher_link}) {{ endfor }}{{ endif }}
+"#;
+
+/// šablona pro seznam učitelů pro renderery kromě HTML
+pub static TEACHER_LIST_PRINT_TEMPLATE: &'static str = r#"
+{{ if pages }}{{ for t in list }}- [{t.jmeno}]({t.username | teacher_link}) - {t.email}
+{{ endfor }}{{ else }}{{ for t in list }} [{t.jmeno}]({t.username | teacher_link}) {{ endfor }}{{ endif }}
 "#;
 
 /// tato struktura existuje jako způsob obcházení limitací `tinytemplate`
 #[derive(Debug, Serialize, Clone)]
 pub struct TeacherList {
     /// karty všech učitelů
-    pub list: Vec<TeacherCard>,
+    pub list:  Vec<TeacherCard>,
+    /// zda mají učitelé vlastní stránky
+    pub pages: bool,
 }
 
 impl Render for TeacherList {
-	fn render(&self, _: &CatContext) -> Result<RenderSite, CatError> {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
 		let render_site = PathBuf::from("teachers.md");
-		let mut tt = TinyTemplate::new();
+		let mut tt = template_engine(context, &render_site);
 
-		tt.add_template("teacher", TEACHER_LIST_TEMPLATE)
+		tt.add_template(
+			"teacher",
+			renderer_template(context, TEACHER_LIST_TEMPLATE, TEACHER_LIST_PRINT_TEMPLATE),
+		)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 		let res = tt
 			.render("teacher", &self)
@@ -166,21 +486,24 @@ impl Render for TeacherList {
 pub static SUBJECT_PRE_TEMPLATE: &'static str = r#"
 | Název | { card.nazev } |
 | ----- | -------------- |
-{{ if resolved_author }}| Zodpovědná osoba |  [{resolved_author.jmeno}](/teachers.md#{resolved_author.username}) | {{ else }}| Zodpovědná osoba | {card.zodpovedna_osoba} | {{ endif }}
-| Popis | { card.bio }   |
+{{ if resolved_author }}| Zodpovědná osoba |  [{resolved_author.jmeno}]({resolved_author.username | teacher_link}) | {{ else }}| Zodpovědná osoba | {card.zodpovedna_osoba} | {{ endif }}
+{{ if teachers }}| Vyučující | {{ for t in teachers }}{{ if not @first }}, {{ endif }}[{t.card.jmeno}]({t.card.username | teacher_link}) ({{ for r in t.roles }}{{ if not @first }}, {{ endif }}{r | role}{{ endfor }}){{ endfor }} |
+{{ endif }}| Popis | { card.bio }   |
+| Rozsah | počet slov: {stats.words}, čtení ~{stats.reading_minutes} min |
+| Obsah | ukázky kódu: {stats.code_blocks}, obrázky: {stats.images}, nadpisy: {stats.headings} |
 "#;
 
 /// šablona seznamu materiálů v daném předmětu (část za obsahem)
 pub static SUBJECT_POST_TEMPLATE: &'static str = r#"
 ### Seznam materiálů
-{{ for a in articles }} - [{a.card.nazev}](/{a.path})
+{{ for a in articles }} - [{a.card.nazev}]({a.path | link}) (čtení ~{a.stats.reading_minutes} min)
 {{ endfor }}
 "#;
 
 impl Render for Subject {
-	fn render(&self, _: &CatContext) -> Result<RenderSite, CatError> {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
 		let render_site = self.path.clone();
-		let mut tt = TinyTemplate::new();
+		let mut tt = template_engine(context, &render_site);
 
 		tt.add_template("subject_pre", SUBJECT_PRE_TEMPLATE)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
@@ -191,9 +514,14 @@ impl Render for Subject {
 			.render("subject_pre", &self)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
-		let post = tt
+		let mut post = tt
 			.render("subject_post", &self)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		post.push_str(&feed_link(
+			context,
+			&render_site,
+			&feed::subject_feed(&context.config, &self.card),
+		));
 
 		dbg!("{}\n{}", &pre, &post);
 
@@ -201,14 +529,91 @@ impl Render for Subject {
 	}
 }
 
+/// položka přehledu předmětů
+///
+/// obsahuje údaje o předmětu, které nejsou
+/// v šabloně přímo dopočitatelné
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectSummary {
+	/// karta předmětu
+	pub card:            SubjectCard,
+	/// cesta k předmětu
+	pub path:            PathBuf,
+	/// karta zodpovědné osoby, pokud se ji podařilo najít
+	pub resolved_author: Option<TeacherCard>,
+	/// počet materiálů v předmětu
+	pub article_count:   usize,
+	/// datum poslední změny některého z materiálů předmětu
+	pub last_update:     Option<String>,
+}
+
+/// tato struktura existuje jako způsob obcházení limitací `tinytemplate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectList {
+	/// přehled všech předmětů
+	pub list: Vec<SubjectSummary>,
+}
+
+/// konverze ze seznamu předmětů na šablonový kontext
+impl From<&Vec<Subject>> for SubjectList {
+	fn from(src: &Vec<Subject>) -> Self {
+		Self {
+			list: src
+				.iter()
+				.map(|s| SubjectSummary {
+					card:            s.card.clone(),
+					path:            s.path.clone(),
+					resolved_author: s.resolved_author.clone(),
+					article_count:   s.articles.len(),
+					last_update:     s
+						.articles
+						.iter()
+						.map(|a| a.last_modified.clone())
+						.max(),
+				})
+				.collect::<Vec<_>>(),
+		}
+	}
+}
+
+/// šablona pro stránku s přehledem předmětů
+pub static SUBJECT_LIST_TEMPLATE: &'static str = r#"
+# Předměty
+{{ for s in list }}
+### [{s.card.nazev}]({s.path | link})
+
+{s.card.bio}
+
+{{ if s.resolved_author }}- Zodpovědná osoba: [{s.resolved_author.jmeno}]({s.resolved_author.username | teacher_link}){{ else }}- Zodpovědná osoba: {s.card.zodpovedna_osoba}{{ endif }}
+- Počet materiálů: {s.article_count}
+{{ if s.last_update }}- Poslední změna: {s.last_update}{{ else }}- Poslední změna: –{{ endif }}
+{{ endfor }}
+"#;
+
+impl Render for SubjectList {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
+		let render_site = PathBuf::from("subjects.md");
+		let mut tt = template_engine(context, &render_site);
+
+		tt.add_template("subjects", SUBJECT_LIST_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		let res = tt
+			.render("subjects", &self)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+
+		Ok(RenderSite::new(render_site, EntirePage(res)))
+	}
+}
+
 /// šablona karty článku (část před obsahem)
 pub static ARTICLE_PRE_TEMPLATE: &'static str = r#"
 | Název | {card.nazev} |
 | ----- | ------------ |
-{{ if resolved_author }}| Autor |  [{resolved_author.jmeno}](/teachers.md#{resolved_author.username}) | {{ else }}| Autor | {author} | {{ endif }}
-{{ if modified_resolved }}| Naposledy upravil |  [{modified_resolved.jmeno}](/teachers.md#{modified_resolved.username}) | {{ else }}| Naposledy upravil | {modified_by} | {{ endif }}
+{{ if resolved_author }}| Autor |  [{resolved_author.jmeno}]({resolved_author.username | teacher_link}) | {{ else }}| Autor | {author} | {{ endif }}
+{{ if modified_resolved }}| Naposledy upravil |  [{modified_resolved.jmeno}]({modified_resolved.username | teacher_link}) | {{ else }}| Naposledy upravil | {modified_by} | {{ endif }}
 | Poslední změna | {last_modified} |
-| Předmět | [{subject_card.nazev}](/{subject_card._resolved_path}) |
+{{ if subject_cards }}| Předmět | {{ for s in subject_cards }}{{ if not @first }}, {{ endif }}[{s.nazev}]({s._resolved_path | link}){{ endfor }} |
+{{ endif }}| Rozsah | počet slov: {stats.words}, čtení ~{stats.reading_minutes} min |
 {{ if card.datum }}| Datum | {card.datum} |{{endif}}
 "#;
 
@@ -218,44 +623,273 @@ pub static ARTICLE_PRE_TEMPLATE: &'static str = r#"
 /// komentářů.
 pub static ARTICLE_POST_TEMPLATE: &'static str = r#"
 #### Tagy
-{{ for tag in card.tagy}} [{tag}](/tags.md#{tag}) {{ endfor }}
+{{ for tag in card.tagy}} [{tag}]({tag | tag_link}) {{ endfor }}
 
 <div id="disqus_thread"></div>
 <script>var disqus_config = function () \{ this.page.url = window.location.href; this.page.identifier = window.location.href; }; (function() \{ var d = document, s = d.createElement('script'); s.src = 'https://gjk-cat.disqus.com/embed.js'; s.setAttribute('data-timestamp', +new Date()); (d.head || d.body).appendChild(s); })(); </script>
 <noscript>Please enable JavaScript to view the <a href="https://disqus.com/?ref_noscript">comments powered by Disqus.</a></noscript>
 "#;
 
+/// šablona seznamu tagů u článku pro renderery kromě HTML,
+/// komentáře se v tištěném výstupu vynechávají
+pub static ARTICLE_POST_PRINT_TEMPLATE: &'static str = r#"
+#### Tagy
+{{ for tag in card.tagy}} [{tag}]({tag | tag_link}) {{ endfor }}
+"#;
+
+/// šablona upozornění na zastaralý článek
+pub static STALE_BANNER_TEMPLATE: &'static str = r#"
+> **Pozor:** tento materiál může být zastaralý{{ if card.revidovat_do }} (revize plánována do {card.revidovat_do}){{ else }} (poslední změna {last_modified}){{ endif }}.
+"#;
+
+/// šablona drobečkové navigace článku
+pub static BREADCRUMBS_TEMPLATE: &'static str = r#"
+{{ if subject }}[Předměty]({index | link}) › [{subject.card.nazev}]({subject.path | link}) › {article.card.nazev}{{ endif }}
+"#;
+
+/// šablona odkazů na předchozí a další materiál v předmětu
+pub static ARTICLE_NAVIGATION_TEMPLATE: &'static str = r#"
+{{ if previous }}- Předchozí materiál: [{previous.card.nazev}]({previous.path | link})
+{{ endif }}{{ if next }}- Další materiál: [{next.card.nazev}]({next.path | link})
+{{ endif }}
+"#;
+
+/// šablonový kontext navigace článku
+///
+/// pořadí článků odpovídá pořadí v [`Subject::articles`],
+/// nikoliv pořadí kapitol v knize
+#[derive(Debug, Clone, Serialize)]
+struct ArticleNavigation<'a> {
+	index:    &'static str,
+	article:  &'a Article,
+	subject:  Option<&'a Subject>,
+	previous: Option<&'a Article>,
+	next:     Option<&'a Article>,
+}
+
+impl<'a> ArticleNavigation<'a> {
+	fn new(article: &'a Article, context: &'a CatContext) -> Self {
+		let subject = context.subject_of(article);
+
+		let position = subject
+			.and_then(|s| s.articles.iter().position(|a| a.path == article.path))
+			.unwrap_or(0);
+
+		ArticleNavigation {
+			index: "subjects.md",
+			article,
+			subject,
+			previous: subject
+				.and_then(|s| position.checked_sub(1).and_then(|i| s.articles.get(i))),
+			next: subject.and_then(|s| s.articles.get(position + 1)),
+		}
+	}
+}
+
 impl Render for Article {
-	fn render(&self, _: &CatContext) -> Result<RenderSite, CatError> {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
 		let render_site = self.path.clone();
-		let mut tt = TinyTemplate::new();
+		let mut tt = template_engine(context, &render_site);
 
 		tt.add_template("article_pre", ARTICLE_PRE_TEMPLATE)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
-		tt.add_template("article_post", ARTICLE_POST_TEMPLATE)
+		tt.add_template(
+			"article_post",
+			renderer_template(context, ARTICLE_POST_TEMPLATE, ARTICLE_POST_PRINT_TEMPLATE),
+		)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("breadcrumbs", BREADCRUMBS_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("navigation", ARTICLE_NAVIGATION_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("stale", STALE_BANNER_TEMPLATE)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
-		let pre = tt
+		let navigation = ArticleNavigation::new(self, context);
+
+		let mut pre = tt
 			.render("article_pre", &self)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
-		let post = tt
+		if self.stale {
+			let banner = tt
+				.render("stale", &self)
+				.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+			pre = format!("{}\n{}", banner, pre);
+		}
+
+		if context.config.breadcrumbs {
+			let breadcrumbs = tt
+				.render("breadcrumbs", &navigation)
+				.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+			pre = format!("{}\n{}", breadcrumbs, pre);
+		}
+
+		let mut post = tt
 			.render("article_post", &self)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
+		if context.config.subject_navigation {
+			let nav = tt
+				.render("navigation", &navigation)
+				.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+			post = format!("{}\n{}", nav, post);
+		}
+
 		dbg!("{}\n{}", &pre, &post);
 
 		Ok(RenderSite::new(render_site, Both(pre, post)))
 	}
 }
 
+/// kontext stránky nedávných změn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentChanges {
+	/// naposledy změněné články, od nejnovějšího
+	pub articles: Vec<Article>,
+}
+
+impl RecentChanges {
+	/// vybere `count` naposledy změněných článků,
+	/// články bez historie v gitu se vynechají
+	pub fn new(articles: &[Article], count: usize) -> Self {
+		let mut articles = articles
+			.iter()
+			.filter(|a| a.modified_timestamp.is_some())
+			.cloned()
+			.collect::<Vec<_>>();
+		articles.sort_by(|a, b| b.modified_timestamp.cmp(&a.modified_timestamp));
+		articles.truncate(count);
+
+		RecentChanges { articles }
+	}
+}
+
+/// šablona stránky nedávných změn
+pub static RECENT_CHANGES_TEMPLATE: &'static str = r#"
+# Nedávné změny
+
+| Datum | Materiál | Předmět | Upravil |
+| ----- | -------- | ------- | ------- |
+{{ for a in articles }}| {a.last_modified} | [{a.card.nazev}]({a.path | link}) | {{ if a.subject_card }}[{a.subject_card.nazev}]({a.subject_card._resolved_path | link}){{ endif }} | {{ if a.modified_resolved }}[{a.modified_resolved.jmeno}]({a.modified_resolved.username | teacher_link}){{ else }}{a.modified_by}{{ endif }} |
+{{ endfor }}
+"#;
+
+impl Render for RecentChanges {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
+		let render_site = PathBuf::from("recent.md");
+		let mut tt = template_engine(context, &render_site);
+
+		tt.add_template("recent", RECENT_CHANGES_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		let mut res = tt
+			.render("recent", &self)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		res.push_str(&feed_link(context, &render_site, &feed::main_feed(&context.config)));
+
+		Ok(RenderSite::new(render_site, EntirePage(res)))
+	}
+}
+
+/// položka stránky údržby
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceEntry {
+	/// zastaralý článek
+	pub article:     Article,
+	/// karta zodpovědné osoby předmětu, pokud ji lze dohledat
+	pub responsible: Option<TeacherCard>,
+	/// zodpovědná osoba předmětu, jak je uvedena v headeru
+	pub zodpovedna:  Option<String>,
+}
+
+/// kontext stránky údržby se zastaralými materiály
+#[derive(Debug, Clone, Serialize)]
+pub struct Maintenance {
+	/// zastaralé články, od nejdéle neupraveného
+	pub articles: Vec<MaintenanceEntry>,
+}
+
+impl Maintenance {
+	/// vybere zastaralé články z kontextu
+	pub fn new(context: &CatContext) -> Self {
+		let mut articles = context
+			.articles
+			.iter()
+			.filter(|a| a.stale)
+			.map(|a| {
+				let subject = context.subject_of(a);
+
+				MaintenanceEntry {
+					article:     a.clone(),
+					responsible: subject.and_then(|s| s.resolved_author.clone()),
+					zodpovedna:  subject.map(|s| s.card.zodpovedna_osoba.clone()),
+				}
+			})
+			.collect::<Vec<_>>();
+		articles.sort_by_key(|a| a.article.modified_timestamp);
+
+		Maintenance { articles }
+	}
+
+	/// zda je stránka údržby potřeba, tj. zda je zapnuté
+	/// hlídání stáří materiálů globálně nebo alespoň u jednoho článku
+	pub fn enabled(context: &CatContext) -> bool {
+		context.config.max_stari.is_some()
+			|| context
+				.articles
+				.iter()
+				.any(|a| a.card.revidovat_po.is_some() || a.card.revidovat_do.is_some())
+	}
+}
+
+/// šablona stránky údržby
+pub static MAINTENANCE_TEMPLATE: &'static str = r#"
+# Údržba
+
+Materiály, které mohou být zastaralé a je potřeba je zrevidovat.
+
+{{ if articles }}| Materiál | Předmět | Zodpovědná osoba | Poslední změna | Revidovat do |
+| -------- | ------- | ---------------- | -------------- | ------------ |
+{{ for e in articles }}| [{e.article.card.nazev}]({e.article.path | link}) | {{ if e.article.subject_card }}[{e.article.subject_card.nazev}]({e.article.subject_card._resolved_path | link}){{ endif }} | {{ if e.responsible }}[{e.responsible.jmeno}]({e.responsible.username | teacher_link}){{ else }}{{ if e.zodpovedna }}{e.zodpovedna}{{ endif }}{{ endif }} | {e.article.last_modified} | {{ if e.article.card.revidovat_do }}{e.article.card.revidovat_do}{{ endif }} |
+{{ endfor }}{{ else }}Žádný materiál není zastaralý.
+{{ endif }}
+"#;
+
+impl Render for Maintenance {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
+		let render_site = PathBuf::from("maintenance.md");
+		let mut tt = template_engine(context, &render_site);
+
+		tt.add_template("maintenance", MAINTENANCE_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		let res = tt
+			.render("maintenance", &self)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+
+		Ok(RenderSite::new(render_site, EntirePage(res)))
+	}
+}
+
 /// struktura obsahující pár tag - články
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
 	/// samotný tag jako string
 	pub name:     String,
+	/// unikátní slug tagu, viz [`tag_slugs`]
+	pub slug:     String,
+	/// velikost písma tagu v oblaku tagů (v procentech),
+	/// odvozená od počtu článků s tímto tagem
+	pub weight:   usize,
 	/// seznam článků s tímto tagem
 	pub articles: Vec<ArticleCard>,
+	/// popisek tagu z `tags.toml`
+	pub popis:    Option<String>,
+	/// barva tagu z `tags.toml`
+	pub barva:    Option<String>,
+	/// aliasy tagu z `tags.toml`
+	pub aliasy:   Vec<String>,
+	/// přímí potomci hierarchického tagu
+	pub children: Vec<String>,
 }
 
 /// tagový kontext pro `tinytemplate` šablonu
@@ -265,6 +899,11 @@ pub struct TagContext {
 	pub tags: Vec<Tag>,
 }
 
+/// nejmenší velikost písma tagu v oblaku tagů (v procentech)
+pub const TAG_WEIGHT_MIN: usize = 80;
+/// největší velikost písma tagu v oblaku tagů (v procentech)
+pub const TAG_WEIGHT_MAX: usize = 200;
+
 /// konverze z tagové hasmapy na šablonový kontext
 impl From<&HashMap<String, Vec<ArticleCard>>> for TagContext {
 	fn from(src: &HashMap<String, Vec<ArticleCard>>) -> Self {
@@ -272,48 +911,437 @@ impl From<&HashMap<String, Vec<ArticleCard>>> for TagContext {
 			src.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>();
 		tags.sort_by(|a, b| a.0.cmp(&b.0));
 
+		let slugs = tag_slugs(src.keys());
+		let min = tags.iter().map(|(_, v)| v.len()).min().unwrap_or(0);
+		let max = tags.iter().map(|(_, v)| v.len()).max().unwrap_or(0);
+		let names = tags.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+
 		Self {
 			tags: tags
 				.into_iter()
-				.map(|(k, v)| Tag { name: k, articles: v })
+				.map(|(k, v)| Tag {
+					slug:     slugs[&k].clone(),
+					weight:   match max - min {
+						0 => 100,
+						range =>
+							TAG_WEIGHT_MIN
+								+ (v.len() - min) * (TAG_WEIGHT_MAX - TAG_WEIGHT_MIN) / range,
+					},
+					children: names
+						.iter()
+						.filter(|x| parent_tag(x) == Some(k.as_str()))
+						.cloned()
+						.collect(),
+					name:     k,
+					articles: v,
+					popis:    None,
+					barva:    None,
+					aliasy:   vec![],
+				})
 				.collect::<Vec<_>>(),
 		}
 	}
 }
 
-/// šablona pro stránku se seznamem tagů a asociovaných článků
+impl TagContext {
+	/// doplní tagům popisky, barvy a aliasy z karet tagů
+	pub fn with_cards(mut self, cards: &HashMap<String, TagCard>) -> Self {
+		self.tags.iter_mut().for_each(|t| {
+			if let Some(card) = find_tag_card(cards, &t.name) {
+				t.popis = card.popis.clone();
+				t.barva = card.barva.clone();
+				t.aliasy = card.aliasy.clone();
+			}
+		});
+
+		self
+	}
+}
+
+/// šablona oblaku tagů
+pub static TAG_CLOUD_TEMPLATE: &'static str = r#"{{ for tag in tags }} [<span style="font-size: {tag.weight}%{{ if tag.barva }}; color: {tag.barva}{{ endif }}">{tag.name}</span>]({tag.name | tag_link}) {{ endfor }}"#;
+
+/// šablona oblaku tagů pro renderery kromě HTML
+pub static TAG_CLOUD_PRINT_TEMPLATE: &'static str =
+	r#"{{ for tag in tags }} [{tag.name}]({tag.name | tag_link}) {{ endfor }}"#;
+
+/// šablona pro stránku s oblakem tagů a seznamem asociovaných článků
+///
+/// pokud jsou zapnuté stránky jednotlivých tagů,
+/// seznam článků se vynechává. Pokud slovník tagů obsahuje
+/// skupiny, je oblak rozdělen podle nich.
+///
+/// Oblak obsahuje pouze tagy nejvyšší úrovně, hierarchické
+/// tagy jsou v rejstříku pod ním
 pub static TAGS_TEMPLATE: &'static str = r#"
 # Tagy
-{{ for tag in tags }} [{tag.name}](#{tag.name}) {{ endfor }}
+{{ if categories }}{{ for c in categories }}
+## {c.nazev}
+{{ call cloud with c }}
+{{ endfor }}{{ else }}{{ call cloud with all }}
+{{ endif }}{{ if index }}
+## Rejstřík
+{{ for e in index }}
+{e.indent}- [{e.name}]({e.path | tag_link}) ({e.count}){{ endfor }}
+{{ endif }}{{ if not pages }}
+{{ for tag in tags }}
+<h3 id="{tag.slug}">{tag.name}</h3>
+{{ if tag.popis }}
+{tag.popis}
+{{ endif }}{{ for a in tag.articles }}
+ - [{a.nazev}]({a._resolved_path | link}){{ endfor }}
+{{ endfor }}
+{{ endif }}
+"#;
 
+/// šablona stránky s tagy pro renderery kromě HTML
+pub static TAGS_PRINT_TEMPLATE: &'static str = r#"
+# Tagy
+{{ if categories }}{{ for c in categories }}
+## {c.nazev}
+{{ call cloud with c }}
+{{ endfor }}{{ else }}{{ call cloud with all }}
+{{ endif }}{{ if index }}
+## Rejstřík
+{{ for e in index }}
+{e.indent}- [{e.name}]({e.path | tag_link}) ({e.count}){{ endfor }}
+{{ endif }}{{ if not pages }}
 {{ for tag in tags }}
-<h3 id="{tag.name}">{tag.name}</h3>
-{{ for a in tag.articles }}
- - [{a.nazev}](/{a._resolved_path}){{ endfor }}
+### {tag.name}
+{{ if tag.popis }}
+{tag.popis}
+{{ endif }}{{ for a in tag.articles }}
+ - [{a.nazev}]({a._resolved_path | link}){{ endfor }}
 {{ endfor }}
+{{ endif }}
 "#;
 
+/// skupina tagů na stránce s tagy
+#[derive(Debug, Clone, Serialize)]
+struct TagCategory<'a> {
+	nazev: String,
+	tags:  Vec<&'a Tag>,
+}
+
+/// položka rejstříku hierarchických tagů
+#[derive(Debug, Clone, Serialize)]
+struct TagIndexEntry<'a> {
+	indent: String,
+	name:   &'a str,
+	path:   &'a str,
+	count:  usize,
+}
+
+/// zploští strom tagů na odsazený seznam
+fn tag_index<'a>(nodes: &'a [TagNode], depth: usize, index: &mut Vec<TagIndexEntry<'a>>) {
+	nodes.iter().for_each(|x| {
+		index.push(TagIndexEntry {
+			indent: "  ".repeat(depth),
+			name:   &x.name,
+			path:   &x.path,
+			count:  x.count,
+		});
+		tag_index(&x.children, depth + 1, index);
+	});
+}
+
+/// šablonový kontext stránky s tagy
+#[derive(Debug, Clone, Serialize)]
+struct TagsPage<'a> {
+	tags:       &'a [Tag],
+	all:        TagCategory<'a>,
+	categories: Vec<TagCategory<'a>>,
+	index:      Vec<TagIndexEntry<'a>>,
+	pages:      bool,
+}
+
+impl TagContext {
+	/// tagy nejvyšší úrovně, tj. bez hierarchických tagů
+	fn roots(&self) -> impl Iterator<Item = &Tag> {
+		self.tags.iter().filter(|x| parent_tag(&x.name).is_none())
+	}
+
+	/// rozdělí tagy nejvyšší úrovně do skupin podle slovníku tagů,
+	/// tagy mimo skupiny jsou ve skupině "Ostatní". Hierarchický tag
+	/// ve slovníku zařadí do skupiny i všechny své předky.
+	///
+	/// Pokud slovník nemá žádné skupiny, vrátí prázdný seznam
+	fn categories(&self, vocabulary: &TagGroup) -> Vec<TagCategory<'_>> {
+		let groups = vocabulary.categories();
+
+		if groups.is_empty() {
+			return vec![];
+		}
+
+		let mut categories = groups
+			.into_iter()
+			.map(|(nazev, tags)| {
+				let tags = tags.iter().map(|x| normalize_tag(x)).collect::<Vec<_>>();
+
+				TagCategory {
+					nazev,
+					tags: self
+						.roots()
+						.filter(|x| {
+							let name = normalize_tag(&x.name);
+							tags.iter().any(|t| tag_prefixes(t).contains(&name.as_str()))
+						})
+						.collect(),
+				}
+			})
+			.filter(|x| !x.tags.is_empty())
+			.collect::<Vec<_>>();
+
+		let other = self
+			.roots()
+			.filter(|x| !categories.iter().any(|c| c.tags.iter().any(|t| t.name == x.name)))
+			.collect::<Vec<_>>();
+
+		if !other.is_empty() {
+			categories.push(TagCategory { nazev: "Ostatní".into(), tags: other });
+		}
+
+		categories
+	}
+}
+
 impl Render for TagContext {
-	fn render(&self, _: &CatContext) -> Result<RenderSite, CatError> {
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
 		let render_site = PathBuf::from("tags.md");
-		let mut tt = TinyTemplate::new();
-
-		tt.add_template("tags", TAGS_TEMPLATE)
+		let mut tt = template_engine(context, &render_site);
+
+		tt.add_template(
+			"cloud",
+			renderer_template(context, TAG_CLOUD_TEMPLATE, TAG_CLOUD_PRINT_TEMPLATE),
+		)
+		.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		tt.add_template("tags", renderer_template(context, TAGS_TEMPLATE, TAGS_PRINT_TEMPLATE))
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+
+		let mut page = TagsPage {
+			tags:       &self.tags,
+			all:        TagCategory { nazev: "Tagy".into(), tags: self.roots().collect() },
+			categories: context
+				.vocabulary
+				.as_ref()
+				.map(|x| self.categories(x))
+				.unwrap_or_default(),
+			index:      vec![],
+			pages:      context.config.tag_pages,
+		};
+
+		if context.tag_tree.iter().any(|x| !x.children.is_empty()) {
+			tag_index(&context.tag_tree, 0, &mut page.index);
+		}
+
 		let res = tt
-			.render("tags", &self)
+			.render("tags", &page)
 			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
 
-		dbg!("{}", &res);
+		Ok(RenderSite::new(render_site, EntirePage(res)))
+	}
+}
+
+/// šablona stránky jednoho tagu
+pub static TAG_TEMPLATE: &'static str = r#"
+# Tag: {tag.name}
+{{ if tag.popis }}
+{tag.popis}
+{{ endif }}{{ if tag.aliasy }}
+Také jako: {{ for a in tag.aliasy }}{{ if not @first }}, {{ endif }}{a}{{ endfor }}
+{{ endif }}{{ if tag.children }}
+Podřazené tagy: {{ for c in tag.children }} [{c}]({c | tag_link}) {{ endfor }}
+{{ endif }}
+| Materiál | Předmět | Datum |
+| -------- | ------- | ----- |
+{{ for a in articles }}| [{a.card.nazev}]({a.path | link}) | {{ if a.subject_card }}[{a.subject_card.nazev}]({a.subject_card._resolved_path | link}){{ endif }} | {{ if a.card.datum }}{a.card.datum}{{ else }}{a.last_modified}{{ endif }} |
+{{ endfor }}
+"#;
+
+/// šablonový kontext stránky jednoho tagu
+#[derive(Debug, Clone, Serialize)]
+struct TagPage<'a> {
+	tag:      &'a Tag,
+	articles: Vec<&'a Article>,
+}
+
+impl Render for Tag {
+	/// vyrenderuje stránku `tags/<slug>.md`.
+	///
+	/// Karty článků v tagu neobsahují předmět,
+	/// proto jsou dohledány plné články v kontextu
+	fn render(&self, context: &CatContext) -> Result<RenderSite, CatError> {
+		let render_site = PathBuf::from(format!("tags/{}.md", self.slug));
+		let mut tt = template_engine(context, &render_site);
+
+		let page = TagPage {
+			tag:      self,
+			articles: self
+				.articles
+				.iter()
+				.filter_map(|x| {
+					context.articles.iter().find(|y| Some(&y.path) == x._resolved_path.as_ref())
+				})
+				.collect::<Vec<_>>(),
+		};
+
+		tt.add_template("tag", TAG_TEMPLATE)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		let mut res = tt
+			.render("tag", &page)
+			.map_err(|e| CatError::TinyError { error: e.to_string() })?;
+		res.push_str(&feed_link(
+			context,
+			&render_site,
+			&feed::tag_feed(&context.config, &self.slug),
+		));
 
 		Ok(RenderSite::new(render_site, EntirePage(res)))
 	}
 }
 
+/// nastaví kapitole a všem jejím podkapitolám
+/// názvy nadřazených kapitol
+fn set_parent_names(chapter: &mut Chapter, parents: Vec<String>) {
+	let mut names = parents.clone();
+	names.push(chapter.name.clone());
+
+	chapter.parent_names = parents;
+	chapter.sub_items.iter_mut().for_each(|x| {
+		if let BookItem::Chapter(c) = x {
+			set_parent_names(c, names.clone());
+		}
+	});
+}
+
+/// pokusí se vyplnit zástupnou kapitolu v `items`,
+/// tj. kapitolu se stejným souborem nebo rozpracovanou
+/// kapitolu se stejným názvem
+///
+/// vrací kapitolu zpět, pokud zástupná kapitola neexistuje
+fn fill_placeholder(items: &mut [BookItem], chapter: Chapter) -> Option<Chapter> {
+	let mut chapter = chapter;
+
+	for item in items.iter_mut() {
+		if let BookItem::Chapter(c) = item {
+			let matches = match &c.path {
+				Some(p) => Some(p) == chapter.path.as_ref(),
+				None => c.name == chapter.name,
+			};
+
+			if matches {
+				let parents = c.parent_names.clone();
+
+				c.path = chapter.path.take();
+				c.content = chapter.content.clone();
+				c.sub_items.append(&mut chapter.sub_items);
+				set_parent_names(c, parents);
+
+				return None;
+			}
+
+			chapter = fill_placeholder(&mut c.sub_items, chapter)?;
+		}
+	}
+
+	Some(chapter)
+}
+
+/// vloží kapitolu za (`under == false`) nebo pod (`under == true`)
+/// kapitolu s daným názvem
+///
+/// vrací kapitolu zpět, pokud kapitola s daným názvem neexistuje
+fn place_near(
+	items: &mut Vec<BookItem>,
+	chapter: Chapter,
+	name: &str,
+	under: bool,
+) -> Option<Chapter> {
+	let mut chapter = chapter;
+	let position = items.iter().position(|x| match x {
+		BookItem::Chapter(c) => c.name == name,
+		_ => false,
+	});
+
+	if let Some(i) = position {
+		if let BookItem::Chapter(c) = &mut items[i] {
+			if under {
+				let mut parents = c.parent_names.clone();
+				parents.push(c.name.clone());
+				set_parent_names(&mut chapter, parents);
+
+				c.sub_items.push(BookItem::Chapter(chapter));
+			} else {
+				set_parent_names(&mut chapter, c.parent_names.clone());
+
+				items.insert(i + 1, BookItem::Chapter(chapter));
+			}
+		}
+
+		return None;
+	}
+
+	for item in items.iter_mut() {
+		if let BookItem::Chapter(c) = item {
+			chapter = place_near(&mut c.sub_items, chapter, name, under)?;
+		}
+	}
+
+	Some(chapter)
+}
+
+/// vloží vygenerovanou kapitolu do knihy
+///
+/// pokud kniha obsahuje zástupnou kapitolu (se stejným souborem,
+/// nebo rozpracovanou se stejným názvem), vyplní ji,
+/// jinak kapitolu umístí podle `placement`
+pub fn insert_chapter(
+	book: &mut Book,
+	chapter: Chapter,
+	placement: &Placement,
+) -> Result<(), CatError> {
+	let mut chapter = match fill_placeholder(&mut book.sections, chapter) {
+		Some(c) => c,
+		None => return Ok(()),
+	};
+
+	let not_found = |chapter: Chapter, name: &str| CatError::ChapterNotFound {
+		chapter: chapter.name,
+		name:    name.to_string(),
+	};
+
+	match placement {
+		Placement::Prefix => {
+			set_parent_names(&mut chapter, vec![]);
+			book.sections.insert(0, BookItem::Chapter(chapter));
+		}
+		Placement::Suffix => {
+			set_parent_names(&mut chapter, vec![]);
+			book.push_item(BookItem::Chapter(chapter));
+		}
+		Placement::After(name) => {
+			if let Some(c) = place_near(&mut book.sections, chapter, name, false) {
+				return Err(not_found(c, name));
+			}
+		}
+		Placement::Under(name) => {
+			if let Some(c) = place_near(&mut book.sections, chapter, name, true) {
+				return Err(not_found(c, name));
+			}
+		}
+	}
+
+	Ok(())
+}
+
 /// vytvoří rendery z objektů
 ///
-/// zároveň založí stránky `teachers.md`
-/// a `tags.md`.
+/// zároveň založí stránky `subjects.md`,
+/// `teachers.md`, `recent.md` a `tags.md`,
+/// popř. stránky jednotlivých vyučujících a tagů.
+///
+/// Chyby se sbírají ze všech renderů, vypíší se všechny
+/// a vrátí se první z nich
 pub fn create_renders(
 	context: &CatContext,
 	book: &mut Book,
@@ -321,9 +1349,14 @@ pub fn create_renders(
 	let mut pending_renders: Vec<RenderSite> = vec![];
 	let mut errors: Vec<CatError> = vec![];
 
-	match (TeacherList { list: context.teacher_cards.clone() }).render(context) {
-    	Ok(r) => pending_renders.push(r),
-    	Err(e) => return Err(e),
+	let teacher_list = TeacherList {
+		list:  context.teacher_cards.clone(),
+		pages: context.config.teacher_pages,
+	};
+
+	match teacher_list.render(context) {
+		Ok(r) => pending_renders.push(r),
+		Err(e) => errors.push(e),
 	}
 
 	context.teachers.iter().for_each(|t| match t.render(context) {
@@ -331,10 +1364,11 @@ pub fn create_renders(
 		Err(e) => errors.push(e),
 	});
 
-	if !errors.is_empty() {
-		errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
-
-		return Err(errors[0].clone());
+	if !context.subjects.is_empty() {
+		match SubjectList::from(&context.subjects).render(context) {
+			Ok(r) => pending_renders.push(r),
+			Err(e) => errors.push(e),
+		}
 	}
 
 	context.subjects.iter().for_each(|t| match t.render(context) {
@@ -342,49 +1376,134 @@ pub fn create_renders(
 		Err(e) => errors.push(e),
 	});
 
-	if !errors.is_empty() {
-		errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
-
-		return Err(errors[0].clone());
-	}
-
 	context.articles.iter().for_each(|t| match t.render(context) {
 		Ok(r) => pending_renders.push(r),
 		Err(e) => errors.push(e),
 	});
 
-	if !errors.is_empty() {
-		errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+	let show_recent = context.config.recent_changes > 0 && !context.articles.is_empty();
 
-		return Err(errors[0].clone());
+	if show_recent {
+		match RecentChanges::new(&context.articles, context.config.recent_changes)
+			.render(context)
+		{
+			Ok(r) => pending_renders.push(r),
+			Err(e) => errors.push(e),
+		}
+	}
+
+	let show_maintenance = Maintenance::enabled(context);
+
+	if show_maintenance {
+		match Maintenance::new(context).render(context) {
+			Ok(r) => pending_renders.push(r),
+			Err(e) => errors.push(e),
+		}
 	}
 
-	match TagContext::from(&context.tags).render(context) {
+	let tag_context = TagContext::from(&context.tags).with_cards(&context.tag_cards);
+
+	match tag_context.render(context) {
 		Ok(r) => pending_renders.push(r),
-		Err(e) => return Err(e),
+		Err(e) => errors.push(e),
+	}
+
+	if context.config.tag_pages {
+		tag_context.tags.iter().for_each(|t| match t.render(context) {
+			Ok(r) => pending_renders.push(r),
+			Err(e) => errors.push(e),
+		});
 	}
 
+	let placement = &context.config.placement;
+
+	if !context.subjects.is_empty() {
+		let chapter =
+			Chapter::new("Předměty", "".to_string(), "subjects.md".to_string(), vec![]);
+
+		if let Err(e) = insert_chapter(book, chapter, &placement.subjects) {
+			errors.push(e);
+		}
+	}
 
 	if !context.teacher_cards.is_empty() {
-    	book.push_item(BookItem::Chapter(Chapter::new(
-    		"Vyučující",
-    		"# Vyučující\n".to_string(),
-    		"teachers.md".to_string(),
-    		vec![],
-    	)));
+		let mut chapter = Chapter::new(
+			"Vyučující",
+			"# Vyučující\n".to_string(),
+			"teachers.md".to_string(),
+			vec![],
+		);
+
+		if context.config.teacher_pages {
+			chapter.sub_items = context
+				.teachers
+				.iter()
+				.map(|t| {
+					BookItem::Chapter(Chapter::new(
+						&t.card.jmeno,
+						"".to_string(),
+						teacher_page(&context.teacher_slug(&t.card.username)),
+						vec!["Vyučující".to_string()],
+					))
+				})
+				.collect::<Vec<_>>();
+		}
+
+		if let Err(e) = insert_chapter(book, chapter, &placement.teachers) {
+			errors.push(e);
+		}
+	}
+
+	if show_recent {
+		let chapter =
+			Chapter::new("Nedávné změny", "".to_string(), "recent.md".to_string(), vec![]);
+
+		if let Err(e) = insert_chapter(book, chapter, &placement.recent) {
+			errors.push(e);
+		}
+	}
+
+	if show_maintenance {
+		let chapter =
+			Chapter::new("Údržba", "".to_string(), "maintenance.md".to_string(), vec![]);
+
+		if let Err(e) = insert_chapter(book, chapter, &placement.maintenance) {
+			errors.push(e);
+		}
 	}
 
 	if !context.tags.is_empty() {
-    	book.push_item(BookItem::Chapter(Chapter::new(
-    		"Tagy",
-    		"".to_string(),
-    		"tags.md".to_string(),
-    		vec![],
-    	)));
+		let mut chapter =
+			Chapter::new("Tagy", "".to_string(), "tags.md".to_string(), vec![]);
+
+		if context.config.tag_pages {
+			chapter.sub_items = tag_context
+				.tags
+				.iter()
+				.map(|t| {
+					BookItem::Chapter(Chapter::new(
+						&t.name,
+						"".to_string(),
+						format!("tags/{}.md", t.slug),
+						vec!["Tagy".to_string()],
+					))
+				})
+				.collect::<Vec<_>>();
+		}
+
+		if let Err(e) = insert_chapter(book, chapter, &placement.tags) {
+			errors.push(e);
+		}
 	}
 
 	dbg!("[cat prep] prerender: {:#?}", &book);
 
+	if !errors.is_empty() {
+		errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+
+		return Err(errors[0].clone());
+	}
+
 	Ok(pending_renders)
 }
 
@@ -398,9 +1517,12 @@ pub fn execute_renders(
 ) -> Result<(), CatError> {
 	book.for_each_mut(|c| {
 		if let BookItem::Chapter(c) = c {
-			let path = c.path.clone();
+			let path = match c.path.clone() {
+				Some(p) => p,
+				None => return,
+			};
 
-			pending_renders.iter().filter(|x| x.site == path.clone().unwrap()).for_each(|x| {
+			pending_renders.iter().filter(|x| x.site == path).for_each(|x| {
 				match &x.render {
 					Prepend(s) => c.content = format!("{}\n{}", c.content, s),
 					Both(pre, post) =>
@@ -410,7 +1532,7 @@ pub fn execute_renders(
 				}
 			});
 
-			pending_renders.retain(|x| x.site != c.path.clone().unwrap());
+			pending_renders.retain(|x| x.site != path);
 		}
 	});
 
@@ -426,3 +1548,46 @@ pub fn execute_renders(
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slugify_transliterates_names() {
+		assert_eq!(slugify("Programování v C++"), "programovani-v-c-plus-plus");
+		assert_eq!(slugify("C#"), "c-sharp");
+		assert_eq!(slugify("  Žluťoučký kůň "), "zlutoucky-kun");
+		assert_eq!(slugify("?!"), "3f-21");
+	}
+
+	#[test]
+	fn colliding_tag_slugs_get_a_suffix() {
+		let tags = ["c sharp", "C#", "c sharp 2", "Rust"].iter().map(|x| x.to_string());
+		let slugs = tag_slugs(&tags.collect::<Vec<_>>());
+
+		assert_eq!(slugs["C#"], "c-sharp");
+		// `c-sharp-2` patří tagu `c sharp 2`
+		assert_eq!(slugs["c sharp"], "c-sharp-3");
+		assert_eq!(slugs["c sharp 2"], "c-sharp-2");
+		assert_eq!(slugs["Rust"], "rust");
+	}
+
+	#[test]
+	fn colliding_usernames_get_distinct_pages_and_anchors() {
+		let usernames = ["jan.novak", "jan-novak"].iter().map(|x| x.to_string());
+		let mut context = CatContext::new();
+		context.teacher_slugs = teacher_slugs(&usernames.collect::<Vec<_>>());
+
+		let page = |username: &str| teacher_page(&context.teacher_slug(username));
+		assert_eq!(page("jan-novak"), Path::new("teachers/jan-novak.md"));
+		assert_eq!(page("jan.novak"), Path::new("teachers/jan-novak-2.md"));
+
+		let mut tt = template_engine(&context, Path::new("a.md"));
+		tt.add_template("t", "{a | teacher_link} {b | teacher_link}").unwrap();
+		let usernames = serde_json::json!({ "a": "jan-novak", "b": "jan.novak" });
+		let res = tt.render("t", &usernames);
+
+		assert_eq!(res.unwrap(), "teachers.md#jan-novak teachers.md#jan-novak-2");
+	}
+}
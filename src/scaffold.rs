@@ -0,0 +1,198 @@
+//! modul obsahující zakládání nových vyučujících, předmětů a článků
+//!
+//! Vytvořené soubory obsahují kostru headeru, kterou stačí
+//! vyplnit. Jméno a email se doplňují z `git config user.*`.
+//! Předměty a články jsou zároveň zapsány do `SUMMARY.md`,
+//! články pod kapitolu svého předmětu.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use mdbook::Config;
+use walkdir::WalkDir;
+
+use crate::cat_context::extract_header;
+use crate::error::CatError;
+use crate::models::*;
+use crate::render::slugify;
+use crate::summary;
+use crate::util::write_if_changed;
+
+/// přečte hodnotu z konfigurace `gitu` v dané složce
+pub fn git_config(root: &Path, key: &str) -> Option<String> {
+	let (status, value, _) =
+		sh!("{}", &format!("git -C '{}' config {}", root.display(), key));
+
+	match value.trim() {
+		x if status == 0 && !x.is_empty() => Some(x.to_string()),
+		_ => None,
+	}
+}
+
+/// vrátí složku se zdrojovými soubory knihy (obvykle `src`)
+pub fn src_dir(root: &Path) -> Result<PathBuf, CatError> {
+	let book_toml = root.join("book.toml");
+
+	if !book_toml.exists() {
+		return Ok(root.join("src"));
+	}
+
+	let config = Config::from_disk(&book_toml).map_err(|e| CatError::BookLoadFailed {
+		path:  root.display().to_string(),
+		error: e.to_string(),
+	})?;
+
+	Ok(root.join(config.book.src))
+}
+
+/// převede hodnotu na toml string
+fn quoted(value: &str) -> String {
+	toml::Value::String(value.to_string()).to_string()
+}
+
+/// zapíše nový soubor, pokud ještě neexistuje
+fn create(path: &Path, content: &str) -> Result<(), CatError> {
+	if path.exists() {
+		return Err(CatError::AlreadyExists { path: path.display().to_string() });
+	}
+
+	write_if_changed(path, content)
+}
+
+/// přečte soubor
+fn read(path: &Path) -> Result<String, CatError> {
+	fs::read_to_string(path).map_err(|e| CatError::ReadFailed {
+		path:  path.display().to_string(),
+		error: e.to_string(),
+	})
+}
+
+/// založí kapitolu a přidá ji do `SUMMARY.md`
+///
+/// nový obsah `SUMMARY.md` se připraví ještě před založením souboru,
+/// pokud se jej pak nepodaří zapsat, založený soubor se opět smaže
+fn create_chapter(
+	src: &Path,
+	name: &str,
+	path: &Path,
+	parent: Option<&Path>,
+	content: &str,
+) -> Result<(), CatError> {
+	let summary_path = src.join("SUMMARY.md");
+	let summary = summary::add_entry(&read(&summary_path)?, name, path, parent)?;
+
+	create(&src.join(path), content)?;
+
+	if let Err(e) = write_if_changed(&summary_path, &summary) {
+		let _ = fs::remove_file(src.join(path));
+		return Err(e);
+	}
+
+	Ok(())
+}
+
+/// založí kartu vyučujícího `teachers/<username>.toml`
+///
+/// nezadané jméno a email se přebírají z `gitu`,
+/// nezadaný username se odvodí ze jména. Pokud je
+/// výsledný username prázdný, vrací chybu
+pub fn new_teacher(
+	root: &Path,
+	username: Option<&str>,
+	jmeno: Option<&str>,
+	email: Option<&str>,
+) -> Result<PathBuf, CatError> {
+	let jmeno = jmeno.map(|x| x.to_string()).or_else(|| git_config(root, "user.name"));
+	let email = email.map(|x| x.to_string()).or_else(|| git_config(root, "user.email"));
+
+	let jmeno = jmeno.unwrap_or_default();
+	let username = username.map(|x| x.to_string()).unwrap_or_else(|| slugify(&jmeno));
+
+	if username.trim().is_empty() {
+		return Err(CatError::MissingUsername);
+	}
+
+	let path = root.join("teachers").join(format!("{}.toml", username));
+	let content = format!(
+		"jmeno = {}\nemail = {}\nusername = {}\nbio = \"\"\"\n\"\"\"\n",
+		quoted(&jmeno),
+		quoted(&email.unwrap_or_default()),
+		quoted(&username),
+	);
+
+	create(&path, &content)?;
+	Ok(path)
+}
+
+/// založí předmět `<dir>/subject.md` a přidá jej do `SUMMARY.md`
+///
+/// `dir` je relativní ke složce `src`, nezadaný název
+/// se odvodí z názvu složky
+pub fn new_subject(root: &Path, dir: &Path, nazev: Option<&str>) -> Result<PathBuf, CatError> {
+	let src = src_dir(root)?;
+	let nazev = nazev.map(|x| x.to_string()).unwrap_or_else(|| {
+		dir.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default()
+	});
+
+	let relative = dir.join("subject.md");
+	let path = src.join(&relative);
+	let content = format!(
+		"nazev = {}\nzodpovedna_osoba = {}\nbio = \"\"\n\n+++\n\n## {}\n",
+		quoted(&nazev),
+		quoted(&git_config(root, "user.name").unwrap_or_default()),
+		nazev,
+	);
+
+	create_chapter(&src, &nazev, &relative, None, &content)?;
+
+	Ok(path)
+}
+
+/// najde předmět podle složky (relativní ke `src`) nebo názvu
+///
+/// vrací cestu k `subject.md` relativní ke `src`
+pub fn find_subject(src: &Path, subject: &str) -> Result<PathBuf, CatError> {
+	let wanted = Path::new(subject.trim_start_matches("./").trim_end_matches('/'));
+
+	WalkDir::new(src)
+		.into_iter()
+		.filter_map(|e| e.ok())
+		.filter(|e| e.file_name() == "subject.md")
+		.filter_map(|e| e.path().strip_prefix(src).ok().map(|p| p.to_path_buf()))
+		.find(|p| {
+			p.parent() == Some(wanted)
+				|| read(&src.join(p))
+					.ok()
+					.and_then(|c| extract_header(&c).ok())
+					.and_then(|(h, _)| toml::de::from_str::<SubjectCard>(&h).ok())
+					.map(|c| c.nazev == subject)
+					.unwrap_or(false)
+		})
+		.ok_or_else(|| CatError::SubjectNotFound { name: subject.to_string() })
+}
+
+/// založí článek v daném předmětu a přidá jej do `SUMMARY.md`
+/// pod kapitolu předmětu
+///
+/// soubor článku se jmenuje podle názvu článku, viz [`slugify`]
+pub fn new_article(root: &Path, subject: &str, nazev: &str) -> Result<PathBuf, CatError> {
+	let src = src_dir(root)?;
+	let subject_path = find_subject(&src, subject)?;
+
+	let relative = subject_path
+		.parent()
+		.unwrap_or_else(|| Path::new(""))
+		.join(format!("{}.md", slugify(nazev)));
+	let path = src.join(&relative);
+	let content = format!(
+		"nazev = {}\ntagy = []\ndatum = {}\n\n+++\n\n# {}\n",
+		quoted(nazev),
+		quoted(&Local::now().format("%-d.%-m.%Y").to_string()),
+		nazev,
+	);
+
+	create_chapter(&src, nazev, &relative, Some(&subject_path), &content)?;
+
+	Ok(path)
+}
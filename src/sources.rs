@@ -0,0 +1,560 @@
+//! modul obsahující zdroje dat pro [`CatContextBuilder`]
+//!
+//! Kontext se sestavuje ze dvou zdrojů:
+//! - [`CardSource`] dodává karty vyučujících, předmětů, článků a tagů
+//! - [`HistorySource`] dodává historii souborů (kdo a kdy je založil a upravil)
+//!
+//! Při sestavování knihy se používají [`BookCards`], které čtou
+//! karty z knihy a složky `teachers`, a [`GitHistory`], která čte
+//! historii z gitu. Pro nástroje, které `cat-prep` vkládají
+//! nebo testují bez skutečného repozitáře, jsou k dispozici
+//! [`MemoryCards`] a [`MemoryHistory`].
+//!
+//! Všechny cesty jsou relativní ke složce `src`.
+//!
+//! [`CatContextBuilder`]: crate::cat_context::CatContextBuilder
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use mdbook::book::{Book, BookItem};
+use serde::{Serialize, Deserialize};
+
+use crate::cat_context::{
+	extract_header, read_tag_cards, read_teacher_cards, read_vocabulary,
+};
+use crate::config::CatConfig;
+use crate::error::CatError;
+use crate::models::*;
+use crate::summary;
+
+/// zdroj karet
+pub trait CardSource {
+	/// karty vyučujících
+	fn teacher_cards(&mut self) -> Result<Vec<TeacherCard>, CatError>;
+
+	/// karty předmětů, každá musí mít vyplněné `_resolved_path`
+	fn subject_cards(&mut self) -> Result<Vec<SubjectCard>, CatError>;
+
+	/// karty článků spolu s jejich obsahem (bez headeru) v pořadí,
+	/// v jakém jsou v knize. Každá musí mít vyplněné `_resolved_path`.
+	///
+	/// `subjects` jsou karty vrácené [`CardSource::subject_cards`]
+	fn article_cards(
+		&mut self,
+		subjects: &[SubjectCard],
+	) -> Result<Vec<(ArticleCard, String)>, CatError>;
+
+	/// karty tagů, klíčem je název tagu
+	fn tag_cards(&mut self) -> Result<HashMap<String, TagCard>, CatError> {
+		Ok(HashMap::new())
+	}
+
+	/// slovník tagů, pokud je nastaven
+	fn vocabulary(&mut self) -> Result<Option<TagGroup>, CatError> {
+		Ok(None)
+	}
+}
+
+/// historie jednoho souboru
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileHistory {
+	/// unixový timestamp poslední změny,
+	/// `None`, pokud soubor nemá historii (např. ještě není v gitu)
+	pub modified_timestamp: Option<i64>,
+	/// datum poslední změny pro zobrazení
+	pub last_modified:      String,
+	/// jméno toho, kdo soubor naposledy upravil
+	pub modified_by:        String,
+	/// unixový timestamp založení souboru,
+	/// pokud není znám, použije se poslední změna
+	pub created_timestamp:  Option<i64>,
+}
+
+/// zdroj historie souborů
+pub trait HistorySource {
+	/// soubory, které vyučující založil
+	fn files_created(&mut self, teacher: &TeacherCard) -> Result<Vec<PathBuf>, CatError>;
+
+	/// soubory, které vyučující založil nebo upravil
+	fn files_modified(&mut self, teacher: &TeacherCard)
+		-> Result<Vec<PathBuf>, CatError>;
+
+	/// historie daného souboru
+	fn file_history(&mut self, path: &Path) -> Result<FileHistory, CatError>;
+}
+
+/// zda je kapitola mimo předměty článkem podle svého headeru,
+/// tj. má klíč `predmet` nebo `samostatny = true`
+fn is_article_header(header: &toml::Value) -> bool {
+	header.get("predmet").is_some()
+		|| header.get("samostatny").and_then(|x| x.as_bool()) == Some(true)
+}
+
+/// karty z knihy
+///
+/// Karty předmětů a článků se čtou z headerů kapitol,
+/// které jsou z knihy zároveň odstraněny. Vyučující, tagy
+/// a slovník se čtou ze souborů relativně k pracovnímu adresáři,
+/// který musí být kořenem knihy. Při čtení článků se vypíše
+/// varování o předmětech a článcích, které chybí v `SUMMARY.md`,
+/// viz [`summary::warn_unlisted`]
+pub struct BookCards<'a> {
+	/// kniha, ze které se čte
+	pub book:   &'a mut Book,
+	/// konfigurace `cat-prepu`
+	pub config: &'a CatConfig,
+}
+
+impl<'a> BookCards<'a> {
+	/// vytvoří zdroj karet z dané knihy
+	pub fn new(book: &'a mut Book, config: &'a CatConfig) -> Self {
+		BookCards { book, config }
+	}
+}
+
+impl<'a> CardSource for BookCards<'a> {
+	fn teacher_cards(&mut self) -> Result<Vec<TeacherCard>, CatError> {
+		read_teacher_cards()
+	}
+
+	fn subject_cards(&mut self) -> Result<Vec<SubjectCard>, CatError> {
+		let mut errors: Vec<_> = vec![];
+		let mut subject_cards = vec![];
+
+		self.book.for_each_mut(|x| {
+			if let BookItem::Chapter(c) = x {
+				let is_subject =
+					c.path.as_ref().and_then(|p| p.to_str()).map(|p| p.ends_with("subject.md"));
+
+				if is_subject == Some(true) {
+					let (header, body) = match extract_header(&c.content) {
+						Ok(hb) => hb,
+						Err(e) => {
+							errors.push(e);
+							return;
+						}
+					};
+					c.content = body;
+
+					let mut card: SubjectCard = match toml::de::from_str(&header) {
+						Ok(c) => c,
+						Err(e) => {
+							errors.push(CatError::InvalidHeaderFormat { err: e });
+							return;
+						}
+					};
+
+					card._resolved_path = c.path.clone();
+
+					subject_cards.push(card);
+				}
+			}
+		});
+
+		if !errors.is_empty() {
+			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+
+			return Err(errors[0].clone());
+		}
+
+		Ok(subject_cards)
+	}
+
+	fn article_cards(
+		&mut self,
+		subjects: &[SubjectCard],
+	) -> Result<Vec<(ArticleCard, String)>, CatError> {
+		let roots = subjects
+			.iter()
+			.filter_map(|x| x._resolved_path.as_ref().and_then(|p| p.parent()))
+			.map(Path::to_path_buf)
+			.collect::<Vec<_>>();
+		let standalone = &self.config.standalone;
+
+		let listed = self
+			.book
+			.iter()
+			.filter_map(|x| match x {
+				BookItem::Chapter(c) => c.path.clone(),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		summary::warn_unlisted(&self.config.src, &listed);
+
+		let mut errors: Vec<_> = vec![];
+		let mut article_cards = vec![];
+
+		self.book.for_each_mut(|x| {
+			if let BookItem::Chapter(c) = x {
+				// rozpracované kapitoly (bez souboru) nemohou být články
+				let path = match &c.path {
+					Some(p) => p.clone(),
+					None => return,
+				};
+
+				let in_subject = roots.iter().any(|y| {
+					path.starts_with(y)
+						&& path.file_name().map(|x| x.to_str().unwrap())
+							!= Some("subject.md")
+				});
+
+				// kapitola mimo předměty je článkem, pokud leží
+				// ve složce `standalone` nebo to určuje její header
+				let in_standalone = !roots.iter().any(|y| path.starts_with(y))
+					&& standalone.iter().any(|d| path.starts_with(d));
+				let required = in_subject || in_standalone;
+
+				// ostatní kapitoly bez platného headeru nejsou články
+				let (header, body) = match extract_header(&c.content) {
+					Ok(hb) => hb,
+					Err(_) if !required => return,
+					Err(e) => {
+						errors.push(e);
+						return;
+					}
+				};
+
+				let header = match toml::de::from_str::<toml::Value>(&header) {
+					Ok(h) => h,
+					Err(_) if !required => return,
+					Err(e) => {
+						errors.push(CatError::InvalidHeaderFormat { err: e });
+						return;
+					}
+				};
+
+				if !required && !is_article_header(&header) {
+					return;
+				}
+				c.content = body;
+
+				let mut card: ArticleCard = match header.try_into() {
+					Ok(c) => c,
+					Err(e) => {
+						errors.push(CatError::InvalidHeaderFormat { err: e });
+						return;
+					}
+				};
+
+				card._resolved_path = Some(path);
+
+				article_cards.push((card, c.content.clone()));
+			}
+		});
+
+		if !errors.is_empty() {
+			errors.iter().for_each(|x| eprintln!("[cat-prep] {}", x));
+
+			return Err(errors[0].clone());
+		}
+
+		// `for_each_mut` prochází podkapitoly dříve než jejich rodiče,
+		// pořadí v knize odpovídá až `listed`
+		article_cards.sort_by_key(|(card, _)| {
+			listed.iter().position(|x| card._resolved_path.as_ref() == Some(x))
+		});
+
+		Ok(article_cards)
+	}
+
+	fn tag_cards(&mut self) -> Result<HashMap<String, TagCard>, CatError> {
+		read_tag_cards()
+	}
+
+	fn vocabulary(&mut self) -> Result<Option<TagGroup>, CatError> {
+		match &self.config.tag_vocabulary {
+			Some(path) => Ok(Some(read_vocabulary(path)?)),
+			None => Ok(None),
+		}
+	}
+}
+
+/// soubory, které v historii změnil jeden autor commitů
+#[derive(Debug, Clone, Default)]
+struct AuthorFiles {
+	/// jméno autora
+	name:     String,
+	/// email autora
+	email:    String,
+	/// soubory, které autor založil
+	created:  Vec<PathBuf>,
+	/// soubory, které autor založil nebo upravil
+	modified: Vec<PathBuf>,
+}
+
+/// historie z gitu
+///
+/// Pracovní adresář musí být kořenem knihy uvnitř git repozitáře
+pub struct GitHistory {
+	/// zdrojová složka knihy relativní ke kořeni knihy
+	pub src: PathBuf,
+	/// soubory podle autorů commitů, viz [`GitHistory::authors`]
+	authors: Option<Vec<AuthorFiles>>,
+}
+
+impl GitHistory {
+	/// ověří, že se pracovní adresář nachází v git repozitáři
+	pub fn new(src: &Path) -> Result<GitHistory, CatError> {
+		let (status, is_inside, error) = sh!("git rev-parse --is-inside-work-tree");
+
+		if status != 0 || !is_inside.trim().parse().unwrap_or(false) {
+			return Err(CatError::NotARepo { error });
+		}
+
+		Ok(GitHistory { src: src.to_path_buf(), authors: None })
+	}
+
+	/// soubory ze složky `src` rozdělené podle autorů commitů
+	///
+	/// celá historie se přečte jediným `git log` při prvním dotazu.
+	/// Soubory jsou seřazené, s cestami relativními ke složce `src`,
+	/// a obsahují pouze ty, které stále existují
+	fn authors(&mut self) -> Result<&[AuthorFiles], CatError> {
+		if self.authors.is_none() {
+			let log = GitHistory::git(
+				"git -c core.quotePath=false log --relative --name-status \
+				 --pretty=format:%x01%an%x01%ae",
+			)?;
+
+			let mut authors: HashMap<&str, AuthorFiles> = HashMap::new();
+			let mut author = None;
+
+			for line in log.lines().filter(|x| !x.is_empty()) {
+				if let Some(x) = line.strip_prefix('\u{1}') {
+					let mut parts = x.splitn(2, '\u{1}');
+					let name = parts.next().unwrap_or("").to_string();
+					let email = parts.next().unwrap_or("").to_string();
+
+					author = Some(authors.entry(x).or_insert_with(|| AuthorFiles {
+						name,
+						email,
+						..AuthorFiles::default()
+					}));
+					continue;
+				}
+
+				// `<status>\t<cesta>`, u přejmenování `<status>\t<stará>\t<nová>`
+				let status = line.split('\t').next().unwrap_or("");
+				let path = PathBuf::from(line.rsplit('\t').next().unwrap_or(""));
+
+				if let Some(files) = author.as_mut() {
+					if status.starts_with('A') {
+						files.created.push(path.clone());
+					}
+					files.modified.push(path);
+				}
+			}
+
+			let src = &self.src;
+			let existing = |files: &mut Vec<PathBuf>| {
+				files.sort();
+				files.dedup();
+				*files = files
+					.iter()
+					.filter(|x| x.exists())
+					.filter_map(|x| x.strip_prefix(src).ok())
+					.map(Path::to_path_buf)
+					.collect();
+			};
+
+			self.authors = Some(
+				authors
+					.into_values()
+					.map(|mut x| {
+						existing(&mut x.created);
+						existing(&mut x.modified);
+						x
+					})
+					.collect(),
+			);
+		}
+
+		Ok(self.authors.as_deref().unwrap_or_default())
+	}
+
+	/// soubory ze složky `src` změněné commity daného vyučujícího,
+	/// `created` omezí výběr na soubory, které vyučující založil
+	///
+	/// autor commitu (jméno a email) se s kartou porovnává v Rustu
+	/// pomocí [`TeacherCard::matches`], tedy jako celý řetězec
+	fn teacher_files(
+		&mut self,
+		teacher: &TeacherCard,
+		created: bool,
+	) -> Result<Vec<PathBuf>, CatError> {
+		let mut files = self
+			.authors()?
+			.iter()
+			.filter(|x| teacher.matches(&x.name) || teacher.matches(&x.email))
+			.flat_map(|x| if created { &x.created } else { &x.modified })
+			.cloned()
+			.collect::<Vec<_>>();
+
+		files.sort();
+		files.dedup();
+
+		Ok(files)
+	}
+
+	/// spustí git příkaz a vrátí jeho výstup
+	fn git(command: &str) -> Result<String, CatError> {
+		let (status, output, error) = sh!("{}", command);
+
+		if status != 0 {
+			return Err(CatError::CommandFailed { status, error, name: "git".into() });
+		}
+
+		Ok(output)
+	}
+}
+
+impl HistorySource for GitHistory {
+	fn files_created(&mut self, teacher: &TeacherCard) -> Result<Vec<PathBuf>, CatError> {
+		self.teacher_files(teacher, true)
+	}
+
+	fn files_modified(&mut self, teacher: &TeacherCard)
+		-> Result<Vec<PathBuf>, CatError> {
+		self.teacher_files(teacher, false)
+	}
+
+	fn file_history(&mut self, path: &Path) -> Result<FileHistory, CatError> {
+		let modified = GitHistory::git(&format!(
+			"git log -1 --pretty=\"format:%ct%n%ci\" -- '{}'",
+			self.src.join(path).display()
+		))?;
+		let modified_by = GitHistory::git(&format!(
+			"git log -s -n1 --pretty='format:%an' -- '{}'",
+			self.src.join(path).display()
+		))?;
+		let created = GitHistory::git(&format!(
+			"git log --diff-filter=A -1 --pretty='format:%ct' -- '{}'",
+			self.src.join(path).display()
+		))?;
+
+		let mut modified = modified.lines();
+		let modified_timestamp = modified.next().and_then(|x| x.trim().parse().ok());
+
+		Ok(FileHistory {
+			modified_timestamp,
+			last_modified: modified.next().unwrap_or("").to_string(),
+			modified_by,
+			created_timestamp: created.trim().parse().ok(),
+		})
+	}
+}
+
+/// karty v paměti
+///
+/// karty se vrací tak, jak jsou, předměty a články
+/// proto musí mít vyplněné `_resolved_path`
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCards {
+	/// karty vyučujících
+	pub teacher_cards: Vec<TeacherCard>,
+	/// karty předmětů
+	pub subject_cards: Vec<SubjectCard>,
+	/// karty článků a jejich obsah, viz [`CardSource::article_cards`]
+	pub article_cards: Vec<(ArticleCard, String)>,
+	/// karty tagů
+	pub tag_cards:     HashMap<String, TagCard>,
+	/// slovník tagů
+	pub vocabulary:    Option<TagGroup>,
+}
+
+impl CardSource for MemoryCards {
+	fn teacher_cards(&mut self) -> Result<Vec<TeacherCard>, CatError> {
+		Ok(self.teacher_cards.clone())
+	}
+
+	fn subject_cards(&mut self) -> Result<Vec<SubjectCard>, CatError> {
+		Ok(self.subject_cards.clone())
+	}
+
+	fn article_cards(
+		&mut self,
+		_subjects: &[SubjectCard],
+	) -> Result<Vec<(ArticleCard, String)>, CatError> {
+		Ok(self.article_cards.clone())
+	}
+
+	fn tag_cards(&mut self) -> Result<HashMap<String, TagCard>, CatError> {
+		Ok(self.tag_cards.clone())
+	}
+
+	fn vocabulary(&mut self) -> Result<Option<TagGroup>, CatError> {
+		Ok(self.vocabulary.clone())
+	}
+}
+
+/// historie v paměti
+///
+/// chybějící záznamy znamenají prázdnou historii
+#[derive(Debug, Clone, Default)]
+pub struct MemoryHistory {
+	/// založené soubory podle username vyučujícího
+	pub files_created:  HashMap<String, Vec<PathBuf>>,
+	/// upravené soubory podle username vyučujícího
+	pub files_modified: HashMap<String, Vec<PathBuf>>,
+	/// historie souborů
+	pub files:          HashMap<PathBuf, FileHistory>,
+}
+
+impl HistorySource for MemoryHistory {
+	fn files_created(&mut self, teacher: &TeacherCard) -> Result<Vec<PathBuf>, CatError> {
+		Ok(self.files_created.get(&teacher.username).cloned().unwrap_or_default())
+	}
+
+	fn files_modified(&mut self, teacher: &TeacherCard)
+		-> Result<Vec<PathBuf>, CatError> {
+		Ok(self.files_modified.get(&teacher.username).cloned().unwrap_or_default())
+	}
+
+	fn file_history(&mut self, path: &Path) -> Result<FileHistory, CatError> {
+		Ok(self.files.get(path).cloned().unwrap_or_default())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use mdbook::book::Chapter;
+
+	fn chapter(path: &str, header: &str, sub_items: Vec<Chapter>) -> Chapter {
+		let mut c = Chapter::new(path, format!("{}\n+++\ntext", header), path, vec![]);
+		c.sub_items = sub_items.into_iter().map(BookItem::Chapter).collect();
+		c
+	}
+
+	fn article(path: &str, nazev: &str, sub_items: Vec<Chapter>) -> Chapter {
+		chapter(path, &format!("nazev = \"{}\"\ntagy = []", nazev), sub_items)
+	}
+
+	#[test]
+	fn article_cards_follow_summary_order() {
+		let mut book = Book::new();
+		book.push_item(chapter(
+			"mat/subject.md",
+			"nazev = \"Matematika\"\nzodpovedna_osoba = \"alice\"\nbio = \"\"",
+			vec![
+				article(
+					"mat/uvod.md",
+					"Úvod",
+					vec![article("mat/uvod/detail.md", "Detail", vec![])],
+				),
+				article("mat/zaver.md", "Závěr", vec![]),
+			],
+		));
+
+		let config = CatConfig::default();
+		let mut cards = BookCards::new(&mut book, &config);
+		let subjects = cards.subject_cards().unwrap();
+		let articles = cards.article_cards(&subjects).unwrap();
+
+		assert_eq!(
+			articles.iter().map(|(c, _)| c.nazev.as_str()).collect::<Vec<_>>(),
+			["Úvod", "Detail", "Závěr"]
+		);
+	}
+}
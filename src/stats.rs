@@ -0,0 +1,287 @@
+//! modul obsahující výpočet statistik materiálů
+//!
+//! Statistiky se počítají z markdownu článku (bez headeru)
+//! pomocí parseru `pulldown-cmark`, který využívá i `mdbook`.
+//! Do počtu slov se nezapočítávají bloky kódu, doba čtení
+//! je odhadnuta podle [`WORDS_PER_MINUTE`].
+//!
+//! Dále modul obsahuje souhrnnou zprávu o knize ([`Report`]),
+//! tj. kolik materiálů kdo založil a upravil a jak jsou na tom
+//! jednotlivé předměty. Zprávu vypisuje příkaz `stats`.
+
+use chrono::{TimeZone, Utc};
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::Serialize;
+
+use crate::cat_context::CatContext;
+use crate::error::CatError;
+use crate::models::*;
+
+/// předpokládaná rychlost čtení (slov za minutu)
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// odhadne dobu čtení v minutách,
+/// neprázdný text trvá alespoň minutu
+// `usize::div_ceil` je stabilní až od Rustu 1.73
+#[allow(clippy::manual_div_ceil)]
+pub fn reading_minutes(words: usize) -> usize {
+	(words + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE
+}
+
+/// zda tag ohraničuje pouze část textu, tj. nerozděluje slova
+fn is_inline(tag: &Tag) -> bool {
+	matches!(
+		tag,
+		Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..)
+	)
+}
+
+/// spočítá statistiky daného markdownu
+///
+/// parser vrací text po částech (např. `foo_bar` jako `foo`, `_`, `bar`),
+/// proto se text nejprve spojí a slova se počítají až v celém textu.
+/// Bloky, zalomení řádků apod. slova oddělují
+pub fn article_stats(markdown: &str) -> ArticleStats {
+	let mut stats = ArticleStats::default();
+	let mut in_code = false;
+	let mut text = String::new();
+
+	for event in Parser::new(markdown) {
+		match event {
+			Event::Start(Tag::CodeBlock(_)) => {
+				stats.code_blocks += 1;
+				in_code = true;
+				text.push(' ');
+			}
+			Event::End(Tag::CodeBlock(_)) => in_code = false,
+			Event::Start(Tag::Image(..)) => stats.images += 1,
+			Event::Start(Tag::Heading(_)) => {
+				stats.headings += 1;
+				text.push(' ');
+			}
+			Event::Start(ref t) | Event::End(ref t) if is_inline(t) => (),
+			Event::Text(t) | Event::Code(t) if !in_code => text.push_str(&t),
+			Event::Text(_) => (),
+			_ => text.push(' '),
+		}
+	}
+
+	stats.words = text.split_whitespace().count();
+	stats.reading_minutes = reading_minutes(stats.words);
+	stats
+}
+
+/// sečte statistiky daných článků
+pub fn total_stats(articles: &[Article]) -> ArticleStats {
+	let mut stats = articles.iter().fold(ArticleStats::default(), |mut acc, a| {
+		acc.words += a.stats.words;
+		acc.code_blocks += a.stats.code_blocks;
+		acc.images += a.stats.images;
+		acc.headings += a.stats.headings;
+		acc
+	});
+
+	stats.reading_minutes = reading_minutes(stats.words);
+	stats
+}
+
+/// souhrn práce jednoho vyučujícího
+#[derive(Debug, Clone, Serialize)]
+pub struct TeacherReport {
+	/// username vyučujícího
+	pub username:          String,
+	/// jméno vyučujícího
+	pub jmeno:             String,
+	/// počet článků, které vyučující založil
+	pub articles_created:  usize,
+	/// počet článků, které vyučující naposledy upravil
+	pub articles_modified: usize,
+	/// názvy předmětů, za které je vyučující zodpovědný
+	pub responsible_for:   Vec<String>,
+}
+
+/// souhrn jednoho předmětu
+#[derive(Debug, Clone, Serialize)]
+pub struct SubjectReport {
+	/// název předmětu
+	pub nazev:           String,
+	/// počet článků
+	pub articles:        usize,
+	/// počet článků, které mají alespoň jeden tag
+	pub tagged_articles: usize,
+	/// počet různých tagů v předmětu
+	pub tags:            usize,
+	/// datum nejnovější změny (`YYYY-MM-DD`)
+	pub newest_update:   Option<String>,
+	/// datum nejstarší poslední změny článku (`YYYY-MM-DD`)
+	pub oldest_update:   Option<String>,
+	/// součet statistik obsahu
+	pub stats:           ArticleStats,
+}
+
+/// souhrnná zpráva o knize, viz příkaz `stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+	/// vyučující seřazení podle jména, včetně těch bez materiálů
+	pub teachers: Vec<TeacherReport>,
+	/// předměty seřazené podle názvu
+	pub subjects: Vec<SubjectReport>,
+}
+
+/// převede unixový timestamp na datum,
+/// `None` pro timestamp mimo rozsah, který `chrono` umí zobrazit
+fn day(timestamp: i64) -> Option<String> {
+	Utc.timestamp_opt(timestamp, 0).single().map(|x| x.format("%Y-%m-%d").to_string())
+}
+
+/// sestaví tabulku, buď v markdownu, nebo zarovnanou pro terminál
+fn table(header: &[&str], rows: &[Vec<String>], markdown: bool) -> String {
+	let widths = header
+		.iter()
+		.enumerate()
+		.map(|(i, h)| {
+			rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0).max(h.chars().count())
+		})
+		.collect::<Vec<_>>();
+
+	let line = |cells: Vec<String>| {
+		let cells = cells
+			.iter()
+			.zip(&widths)
+			.map(|(c, w)| format!("{}{}", c, " ".repeat(w - c.chars().count())))
+			.collect::<Vec<_>>();
+
+		if markdown {
+			format!("| {} |", cells.join(" | "))
+		} else {
+			cells.join("  ").trim_end().to_string()
+		}
+	};
+
+	let mut res = vec![line(header.iter().map(|x| x.to_string()).collect())];
+	res.push(if markdown {
+		format!("|{}|", widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>().join("|"))
+	} else {
+		widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("  ")
+	});
+	res.extend(rows.iter().map(|r| line(r.clone())));
+
+	res.join("\n") + "\n"
+}
+
+impl Report {
+	/// sestaví zprávu z kontextu
+	pub fn new(context: &CatContext) -> Report {
+		let teachers = context
+			.teachers
+			.iter()
+			.map(|t| TeacherReport {
+				username:          t.card.username.clone(),
+				jmeno:             t.card.jmeno.clone(),
+				articles_created:  t.articles.len(),
+				articles_modified: context
+					.articles
+					.iter()
+					.filter(|a| {
+						a.modified_resolved.as_ref().map(|x| &x.username) == Some(&t.card.username)
+					})
+					.count(),
+				responsible_for:   t
+					.subjects_with(Role::Responsible)
+					.iter()
+					.map(|s| s.card.nazev.clone())
+					.collect(),
+			})
+			.collect();
+
+		let subjects = context
+			.subjects
+			.iter()
+			.map(|s| {
+				let mut tags = s.articles.iter().flat_map(|a| a.card.tagy.iter()).collect::<Vec<_>>();
+				tags.sort();
+				tags.dedup();
+
+				// články bez historie v gitu se nezapočítávají
+				let updates = s.articles.iter().filter_map(|a| a.modified_timestamp);
+
+				SubjectReport {
+					nazev:           s.card.nazev.clone(),
+					articles:        s.articles.len(),
+					tagged_articles: s.articles.iter().filter(|a| !a.card.tagy.is_empty()).count(),
+					tags:            tags.len(),
+					newest_update:   updates.clone().max().and_then(day),
+					oldest_update:   updates.min().and_then(day),
+					stats:           s.stats.clone(),
+				}
+			})
+			.collect();
+
+		Report { teachers, subjects }
+	}
+
+	/// převede zprávu na text, buď v markdownu, nebo jako tabulky pro terminál
+	pub fn to_text(&self, markdown: bool) -> String {
+		let heading = |h: &str| if markdown { format!("## {}\n\n", h) } else { format!("{}\n\n", h) };
+
+		let teachers = self
+			.teachers
+			.iter()
+			.map(|t| {
+				vec![
+					t.jmeno.clone(),
+					t.username.clone(),
+					t.articles_created.to_string(),
+					t.articles_modified.to_string(),
+					t.responsible_for.join(", "),
+				]
+			})
+			.collect::<Vec<_>>();
+
+		let subjects = self
+			.subjects
+			.iter()
+			.map(|s| {
+				vec![
+					s.nazev.clone(),
+					s.articles.to_string(),
+					format!("{}/{}", s.tagged_articles, s.articles),
+					s.tags.to_string(),
+					s.newest_update.clone().unwrap_or_else(|| "-".into()),
+					s.oldest_update.clone().unwrap_or_else(|| "-".into()),
+					s.stats.words.to_string(),
+				]
+			})
+			.collect::<Vec<_>>();
+
+		format!(
+			"{}{}\n{}{}",
+			heading("Vyučující"),
+			table(
+				&["Jméno", "Username", "Založeno", "Naposledy upraveno", "Zodpovídá za"],
+				&teachers,
+				markdown
+			),
+			heading("Předměty"),
+			table(
+				&[
+					"Předmět",
+					"Materiálů",
+					"S tagy",
+					"Tagů",
+					"Nejnovější změna",
+					"Nejstarší změna",
+					"Slov"
+				],
+				&subjects,
+				markdown
+			),
+		)
+	}
+
+	/// převede zprávu na JSON
+	pub fn to_json(&self) -> Result<String, CatError> {
+		serde_json::to_string_pretty(self)
+			.map_err(|e| CatError::ExportFailed { error: e.to_string() })
+	}
+}
@@ -0,0 +1,470 @@
+//! modul obsahující úpravy souboru `SUMMARY.md`
+//!
+//! `mdbook` neposkytuje API pro zápis `SUMMARY.md`,
+//! proto se soubor upravuje po řádcích. Za kapitolu
+//! je považována pouze položka seznamu ve tvaru
+//! `- [název](cesta)` (popř. s `*`), ostatní řádky
+//! zůstávají beze změny.
+//!
+//! Modul také hledá předměty a články, které jsou na disku,
+//! ale chybí v `SUMMARY.md`, a tudíž je `mdbook` ani `cat-prep`
+//! nevidí, viz [`find_unlisted`] a [`sync`].
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::cat_context::{extract_header, parse_datum};
+use crate::error::CatError;
+use crate::models::*;
+use crate::scaffold::src_dir;
+use crate::util::write_if_changed;
+
+/// šířka tabulátoru při porovnávání odsazení
+const TAB_WIDTH: usize = 4;
+
+/// kapitola v `SUMMARY.md`
+#[derive(Debug, Clone)]
+pub struct SummaryEntry {
+	/// index řádku v souboru
+	pub line:   usize,
+	/// odsazení položky
+	pub indent: String,
+	/// název kapitoly
+	pub name:   String,
+	/// cesta ke kapitole relativní ke složce `src`,
+	/// rozpracované kapitoly cestu nemají
+	pub path:   Option<PathBuf>,
+}
+
+impl SummaryEntry {
+	/// šířka odsazení, tabulátory se počítají jako čtyři mezery
+	pub fn width(&self) -> usize {
+		indent_width(&self.indent)
+	}
+}
+
+/// spočítá šířku odsazení
+fn indent_width(indent: &str) -> usize {
+	indent.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+/// normalizuje cestu z odkazu v `SUMMARY.md`
+fn normalize(path: &str) -> PathBuf {
+	let path = path.trim().trim_start_matches('<').trim_end_matches('>');
+	PathBuf::from(path.trim_start_matches("./").replace("%20", " "))
+}
+
+/// zpracuje jeden řádek `SUMMARY.md`
+fn parse_entry(line: usize, text: &str) -> Option<SummaryEntry> {
+	let trimmed = text.trim_start();
+	let indent = text[..text.len() - trimmed.len()].to_string();
+
+	let rest = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* "))?;
+	let rest = rest.trim_start().strip_prefix('[')?;
+
+	let name_end = rest.find("](")?;
+	let target = &rest[name_end + 2..];
+	let target = &target[..target.rfind(')')?];
+
+	Some(SummaryEntry {
+		line,
+		indent,
+		name: rest[..name_end].to_string(),
+		path: if target.trim().is_empty() { None } else { Some(normalize(target)) },
+	})
+}
+
+/// vrátí všechny kapitoly v `SUMMARY.md`
+pub fn entries(summary: &str) -> Vec<SummaryEntry> {
+	summary.lines().enumerate().filter_map(|(i, l)| parse_entry(i, l)).collect()
+}
+
+/// vrátí index řádku za poslední podkapitolou dané kapitoly
+fn block_end(lines: &[&str], entry: &SummaryEntry) -> usize {
+	let mut end = entry.line + 1;
+
+	for (i, line) in lines.iter().enumerate().skip(entry.line + 1) {
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		match parse_entry(i, line) {
+			Some(e) if e.width() > entry.width() => end = i + 1,
+			_ => break,
+		}
+	}
+
+	end
+}
+
+/// zjistí, jakou jednotkou odsazení soubor používá
+fn indent_unit(entries: &[SummaryEntry]) -> String {
+	entries
+		.iter()
+		.filter(|e| !e.indent.is_empty())
+		.min_by_key(|e| e.width())
+		.map(|e| e.indent.clone())
+		.unwrap_or_else(|| "\t".into())
+}
+
+/// zjistí, jaké konce řádků soubor používá
+fn line_ending(src: &str) -> &'static str {
+	if src.contains("\r\n") {
+		"\r\n"
+	} else {
+		"\n"
+	}
+}
+
+/// vytvoří odkaz na kapitolu ve tvaru používaném v `SUMMARY.md`
+fn summary_link(path: &Path) -> String {
+	format!("./{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+/// přidá do `SUMMARY.md` kapitolu
+///
+/// pokud je `parent` zadán, kapitola se přidá jako poslední
+/// podkapitola kapitoly s danou cestou, jinak se přidá
+/// za poslední kapitolu nejvyšší úrovně.
+/// Konce řádků (`\n` nebo `\r\n`) zůstávají zachovány.
+pub fn add_entry(
+	summary: &str,
+	name: &str,
+	path: &Path,
+	parent: Option<&Path>,
+) -> Result<String, CatError> {
+	let all = entries(summary);
+	let lines = summary.lines().collect::<Vec<_>>();
+
+	let (position, indent) = match parent {
+		Some(parent) => {
+			let entry = all
+				.iter()
+				.find(|e| e.path.as_deref() == Some(parent))
+				.ok_or_else(|| CatError::SummaryEntryNotFound {
+					path: parent.display().to_string(),
+				})?;
+
+			let indent = all
+				.iter()
+				.find(|e| e.line > entry.line && e.width() > entry.width())
+				.filter(|e| e.line < block_end(&lines, entry))
+				.map(|e| e.indent.clone())
+				.unwrap_or_else(|| format!("{}{}", entry.indent, indent_unit(&all)));
+
+			(block_end(&lines, entry), indent)
+		}
+		None => {
+			let top = all.iter().map(|e| e.width()).min().unwrap_or(0);
+
+			match all.iter().rev().find(|e| e.width() == top) {
+				Some(e) => (block_end(&lines, e), e.indent.clone()),
+				None => (lines.len(), "".into()),
+			}
+		}
+	};
+
+	let mut res = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+	res.insert(position, format!("{}- [{}]({})", indent, name, summary_link(path)));
+
+	Ok(res.join(line_ending(summary)) + line_ending(summary))
+}
+
+/// přidá do `SUMMARY.md` podkapitolu kapitoly `parent`
+///
+/// kapitola se vloží před první přímou podkapitolu, pro jejíž
+/// cestu vrátí `before` `true`, jinak se přidá jako poslední
+/// podkapitola, viz [`add_entry`]
+pub fn insert_entry(
+	summary: &str,
+	name: &str,
+	path: &Path,
+	parent: &Path,
+	before: impl Fn(&Path) -> bool,
+) -> Result<String, CatError> {
+	let all = entries(summary);
+	let lines = summary.lines().collect::<Vec<_>>();
+
+	let entry = all.iter().find(|e| e.path.as_deref() == Some(parent)).ok_or_else(|| {
+		CatError::SummaryEntryNotFound { path: parent.display().to_string() }
+	})?;
+	let end = block_end(&lines, entry);
+
+	let children = all
+		.iter()
+		.filter(|e| e.line > entry.line && e.line < end && e.width() > entry.width())
+		.collect::<Vec<_>>();
+	// přímé podkapitoly jsou odsazené nejméně
+	let width = children.iter().map(|e| e.width()).min();
+
+	let next = children
+		.iter()
+		.filter(|e| Some(e.width()) == width)
+		.find(|e| e.path.as_deref().map(&before).unwrap_or(false));
+
+	let next = match next {
+		Some(e) => e,
+		None => return add_entry(summary, name, path, Some(parent)),
+	};
+
+	let mut res = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+	res.insert(next.line, format!("{}- [{}]({})", next.indent, name, summary_link(path)));
+
+	Ok(res.join(line_ending(summary)) + line_ending(summary))
+}
+
+/// předmět nebo článek, který chybí v `SUMMARY.md`
+#[derive(Debug, Clone)]
+pub struct UnlistedChapter {
+	/// název z headeru
+	pub nazev:   String,
+	/// cesta relativní ke složce `src`
+	pub path:    PathBuf,
+	/// cesta k `subject.md` předmětu, pod který článek patří,
+	/// u předmětů je `None`
+	pub subject: Option<PathBuf>,
+}
+
+/// přečte header daného souboru
+fn read_card<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+	let content = fs::read_to_string(path).ok()?;
+	let (header, _) = extract_header(&content).ok()?;
+
+	toml::de::from_str(&header).ok()
+}
+
+/// porovná dva články podle řazení jejich předmětu
+///
+/// u řazení, která vyžadují historii (`last_modified`)
+/// nebo pořadí v `SUMMARY.md`, se porovnává podle názvu
+fn compare_articles(a: &ArticleCard, b: &ArticleCard, razeni: Razeni) -> Ordering {
+	let by_name = a.nazev.cmp(&b.nazev);
+
+	match razeni {
+		Razeni::Datum => {
+			let date = |x: &ArticleCard| {
+				let date = x.datum.as_ref().and_then(|x| parse_datum(x));
+				(date.is_none(), date)
+			};
+
+			date(a).cmp(&date(b)).then(by_name)
+		}
+		Razeni::Poradi => {
+			(a.poradi.is_none(), a.poradi).cmp(&(b.poradi.is_none(), b.poradi)).then(by_name)
+		}
+		_ => by_name,
+	}
+}
+
+/// najde předměty a články ve složce `src`, které nejsou
+/// mezi kapitolami `listed` (cesty relativní ke `src`)
+///
+/// za předmět je považován každý `subject.md` s platným headerem,
+/// za článek každý markdown soubor s platným headerem článku
+/// uvnitř složky předmětu. Soubory bez headeru (např. části
+/// vkládané pomocí `{{#include}}`) se ignorují.
+///
+/// Předměty jsou vráceny první, seřazené podle názvu,
+/// články následují v pořadí daném řazením jejich předmětu.
+pub fn find_unlisted(src: &Path, listed: &[PathBuf]) -> Vec<UnlistedChapter> {
+	let files = WalkDir::new(src)
+		.into_iter()
+		.filter_map(|e| e.ok())
+		.filter(|e| e.file_type().is_file())
+		.filter(|e| e.path().extension().map(|x| x == "md").unwrap_or(false))
+		.filter_map(|e| e.path().strip_prefix(src).ok().map(|p| p.to_path_buf()))
+		.collect::<Vec<_>>();
+
+	let mut subjects = files
+		.iter()
+		.filter(|p| p.file_name().map(|x| x == "subject.md").unwrap_or(false))
+		.filter_map(|p| read_card::<SubjectCard>(&src.join(p)).map(|c| (p.clone(), c)))
+		.collect::<Vec<_>>();
+	subjects.sort_by(|a, b| a.1.nazev.cmp(&b.1.nazev));
+
+	let mut res = subjects
+		.iter()
+		.filter(|(p, _)| !listed.contains(p))
+		.map(|(p, c)| UnlistedChapter { nazev: c.nazev.clone(), path: p.clone(), subject: None })
+		.collect::<Vec<_>>();
+
+	for (subject_path, subject) in &subjects {
+		let root = subject_path.parent().unwrap_or_else(|| Path::new(""));
+
+		let mut articles = files
+			.iter()
+			.filter(|p| p.starts_with(root) && p != &subject_path && !listed.contains(p))
+			.filter(|p| {
+				// článek patří nejbližšímu předmětu
+				!subjects.iter().any(|(s, _)| {
+					let r = s.parent().unwrap_or_else(|| Path::new(""));
+					r != root && r.starts_with(root) && p.starts_with(r)
+				})
+			})
+			.filter_map(|p| read_card::<ArticleCard>(&src.join(p)).map(|c| (p.clone(), c)))
+			.map(|(p, c)| {
+				let chapter = UnlistedChapter {
+					nazev:   c.nazev.clone(),
+					path:    p,
+					subject: Some(subject_path.clone()),
+				};
+
+				(chapter, c)
+			})
+			.collect::<Vec<_>>();
+
+		articles.sort_by(|a, b| compare_articles(&a.1, &b.1, subject.razeni));
+		res.extend(articles.into_iter().map(|(chapter, _)| chapter));
+	}
+
+	res
+}
+
+/// vypíše varování o předmětech a článcích, které chybí v `SUMMARY.md`
+pub fn warn_unlisted(src: &Path, listed: &[PathBuf]) {
+	find_unlisted(src, listed).iter().for_each(|x| match &x.subject {
+		Some(_) => eprintln!(
+			"[cat-prep] warning: article {} ('{}') is not in SUMMARY.md",
+			x.path.display(),
+			x.nazev
+		),
+		None => eprintln!(
+			"[cat-prep] warning: subject {} ('{}') is not in SUMMARY.md",
+			x.path.display(),
+			x.nazev
+		),
+	});
+}
+
+/// přidá chybějící článek mezi podkapitoly jeho předmětu
+///
+/// článek se vloží na místo dané řazením předmětu, tj. před první
+/// článek, který se řadí za něj. U řazení `summary` a `last_modified`
+/// se přidává jako poslední podkapitola
+fn add_article(
+	src: &Path,
+	summary: &str,
+	chapter: &UnlistedChapter,
+	subject: &Path,
+) -> Result<String, CatError> {
+	let razeni =
+		read_card::<SubjectCard>(&src.join(subject)).map(|x| x.razeni).unwrap_or_default();
+	let card = match razeni {
+		Razeni::Summary | Razeni::LastModified => None,
+		_ => read_card::<ArticleCard>(&src.join(&chapter.path)),
+	};
+
+	insert_entry(summary, &chapter.nazev, &chapter.path, subject, |path| {
+		card.as_ref()
+			.and_then(|c| {
+				read_card::<ArticleCard>(&src.join(path))
+					.map(|x| compare_articles(&x, c, razeni) == Ordering::Greater)
+			})
+			.unwrap_or(false)
+	})
+}
+
+/// doplní do `SUMMARY.md` knihy v dané složce chybějící předměty a články
+///
+/// předměty se přidávají na konec seznamu kapitol, články
+/// mezi podkapitoly svého předmětu podle jeho řazení, viz [`add_article`].
+/// Pokud je `dry_run` nastaveno, `SUMMARY.md` se nezmění.
+/// Vrací přidané kapitoly.
+pub fn sync(root: &Path, dry_run: bool) -> Result<Vec<UnlistedChapter>, CatError> {
+	let src = src_dir(root)?;
+	let summary_path = src.join("SUMMARY.md");
+
+	let mut summary = fs::read_to_string(&summary_path).map_err(|e| CatError::ReadFailed {
+		path:  summary_path.display().to_string(),
+		error: e.to_string(),
+	})?;
+
+	let listed = entries(&summary).into_iter().filter_map(|x| x.path).collect::<Vec<_>>();
+	let unlisted = find_unlisted(&src, &listed);
+
+	for chapter in &unlisted {
+		summary = match &chapter.subject {
+			Some(subject) => add_article(&src, &summary, chapter, subject)?,
+			None => add_entry(&summary, &chapter.nazev, &chapter.path, None)?,
+		};
+	}
+
+	if !dry_run && !unlisted.is_empty() {
+		write_if_changed(&summary_path, &summary)?;
+	}
+
+	Ok(unlisted)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn added_entries_follow_summary_indentation() {
+		let a = Path::new("a/subject.md");
+		let summary = "# Obsah\n\n\
+		               - [A](a/subject.md)\n  - [A1](a/1.md)\n\
+		               - [B](b/subject.md)\n";
+
+		assert_eq!(
+			add_entry(summary, "A2", Path::new("a/2.md"), Some(a)).unwrap(),
+			"# Obsah\n\n\
+			 - [A](a/subject.md)\n  - [A1](a/1.md)\n  - [A2](./a/2.md)\n\
+			 - [B](b/subject.md)\n"
+		);
+
+		let b = Path::new("b/subject.md");
+		let res = add_entry(summary, "B1", Path::new("b/1.md"), Some(b)).unwrap();
+		assert!(res.ends_with("- [B](b/subject.md)\n  - [B1](./b/1.md)\n"));
+
+		let res = add_entry(summary, "C", Path::new("c/subject.md"), None).unwrap();
+		assert!(res.ends_with("- [B](b/subject.md)\n- [C](./c/subject.md)\n"));
+
+		// bez podkapitol se odsazuje tabulátorem
+		let summary = "- [A](a/subject.md)\n";
+		assert_eq!(
+			add_entry(summary, "A1", Path::new("a/1.md"), Some(a)).unwrap(),
+			"- [A](a/subject.md)\n\t- [A1](./a/1.md)\n"
+		);
+	}
+
+	#[test]
+	fn added_entries_keep_crlf() {
+		let a = Path::new("a/subject.md");
+		let summary = "# Obsah\r\n\r\n- [A](a/subject.md)\r\n\t- [A1](a/1.md)\r\n";
+
+		assert_eq!(
+			add_entry(summary, "A2", Path::new("a/2.md"), Some(a)).unwrap(),
+			"# Obsah\r\n\r\n- [A](a/subject.md)\r\n\
+			 \t- [A1](a/1.md)\r\n\t- [A2](./a/2.md)\r\n"
+		);
+	}
+
+	#[test]
+	fn missing_parent_is_an_error() {
+		let c = Path::new("c.md");
+		assert!(add_entry("- [A](a.md)\n", "B", Path::new("b.md"), Some(c)).is_err());
+	}
+
+	#[test]
+	fn inserted_entries_keep_order() {
+		let a = Path::new("a/subject.md");
+		let summary = "- [A](a/subject.md)\n\t- [A1](a/1.md)\n\t\t- [X](a/x.md)\n\
+		               \t- [A3](a/3.md)\n- [B](b/subject.md)\n";
+		let before = |p: &Path| p > Path::new("a/2.md");
+
+		assert_eq!(
+			insert_entry(summary, "A2", Path::new("a/2.md"), a, before).unwrap(),
+			"- [A](a/subject.md)\n\t- [A1](a/1.md)\n\t\t- [X](a/x.md)\n\
+			 \t- [A2](./a/2.md)\n\t- [A3](a/3.md)\n- [B](b/subject.md)\n"
+		);
+
+		// bez následující podkapitoly se článek přidá na konec
+		let res = insert_entry(summary, "A4", Path::new("a/4.md"), a, |_| false).unwrap();
+		assert!(res.ends_with("\t- [A4](./a/4.md)\n- [B](b/subject.md)\n"));
+	}
+}
@@ -0,0 +1,581 @@
+//! modul obsahující hromadné úpravy tagů
+//!
+//! Přejmenování a slučování tagů přepisuje pole `tagy`
+//! v headerech všech článků ve složce `src` pomocí `toml_edit`,
+//! takže zbytek headeru (komentáře, pořadí, formátování)
+//! zůstane beze změny.
+//!
+//! Dále obsahuje normalizaci tagů - tagy, které se liší pouze
+//! velikostí písmen nebo Unicode zápisem, jsou při sestavování
+//! knihy sloučeny do jednoho, viz [`canonicalize_tags`],
+//! strom hierarchických tagů (`programování/rust/ownership`), viz [`tag_tree`],
+//! a kontrolu tagů proti slovníku, viz [`check_vocabulary`].
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::Document;
+use unicode_normalization::UnicodeNormalization;
+use walkdir::WalkDir;
+
+use crate::error::CatError;
+use crate::models::{ArticleCard, TagCard, TagNode};
+use crate::scaffold::src_dir;
+use crate::util::write_if_changed;
+
+/// sjednotí bílé znaky tagu a převede jej do Unicode NFC
+///
+/// u hierarchických tagů jsou odstraněny mezery okolo `/`
+/// a prázdné části cesty
+fn clean_tag(tag: &str) -> String {
+	tag.split('/')
+		.map(|x| x.split_whitespace().collect::<Vec<_>>().join(" "))
+		.filter(|x| !x.is_empty())
+		.collect::<Vec<_>>()
+		.join("/")
+		.nfc()
+		.collect()
+}
+
+/// znormalizuje tag pro porovnávání
+///
+/// na tag je použit Unicode case folding (např. `ß` a `ss`
+/// jsou stejné, na rozdíl od převodu na malá písmena) a je převeden
+/// do Unicode NFC, posloupnosti bílých znaků jsou nahrazeny jednou mezerou
+pub fn normalize_tag(tag: &str) -> String {
+	caseless::default_case_fold_str(&clean_tag(tag)).nfc().collect()
+}
+
+/// všechny předky hierarchického tagu včetně tagu samotného,
+/// od kořene, např. `a`, `a/b`, `a/b/c` pro tag `a/b/c`
+pub fn tag_prefixes(tag: &str) -> Vec<&str> {
+	tag.match_indices('/').map(|(i, _)| &tag[..i]).chain(Some(tag)).collect()
+}
+
+/// přímý předek hierarchického tagu
+pub fn parent_tag(tag: &str) -> Option<&str> {
+	tag.rfind('/').map(|i| &tag[..i])
+}
+
+/// poslední část cesty hierarchického tagu
+pub fn tag_leaf(tag: &str) -> &str {
+	tag.rsplit('/').next().unwrap_or(tag)
+}
+
+/// najde kartu tagu podle normalizovaného názvu
+pub fn find_tag_card<'a>(
+	tag_cards: &'a HashMap<String, TagCard>,
+	tag: &str,
+) -> Option<&'a TagCard> {
+	let tag = normalize_tag(tag);
+
+	tag_cards.iter().find(|(k, _)| normalize_tag(k) == tag).map(|(_, v)| v)
+}
+
+/// sjednotí tagy článků na jejich kanonický tvar
+///
+/// kanonickým tvarem je název tagu v `tags.toml`, pokud mu
+/// tag nebo některý z jeho aliasů po normalizaci odpovídá,
+/// dále zápis ve slovníku tagů (`vocabulary`),
+/// jinak nejčastější zápis tagu v knize (při shodě abecedně první).
+///
+/// U hierarchických tagů se kanonický tvar určuje pro každou
+/// část cesty zvlášť, takže `Programování/rust` a `programování/python`
+/// mají společného předka. Duplicitní tagy jednoho článku jsou odstraněny
+pub fn canonicalize_tags(
+	cards: &mut [ArticleCard],
+	tag_cards: &HashMap<String, TagCard>,
+	vocabulary: &[String],
+) {
+	let mut declared = tag_cards.iter().collect::<Vec<_>>();
+	declared.sort_by(|a, b| a.0.cmp(b.0));
+
+	// normalizovaný tag -> zobrazovaný tvar
+	let mut display: HashMap<String, String> = HashMap::new();
+	// normalizovaný alias -> normalizovaný tag
+	let mut aliases: HashMap<String, String> = HashMap::new();
+
+	declared.iter().for_each(|(name, _)| {
+		display.entry(normalize_tag(name)).or_insert_with(|| clean_tag(name));
+	});
+
+	for (name, card) in &declared {
+		for alias in &card.aliasy {
+			let key = normalize_tag(alias);
+			let other = aliases
+				.get(&key)
+				.and_then(|x| display.get(x))
+				.or_else(|| display.get(&key))
+				.cloned();
+
+			match other {
+				Some(other) if normalize_tag(&other) != normalize_tag(name) => eprintln!(
+					"[cat-prep] tag alias '{}' of '{}' ignored, it already belongs to '{}'",
+					alias, name, other
+				),
+				Some(_) => (),
+				None => {
+					aliases.insert(key, normalize_tag(name));
+				}
+			}
+		}
+	}
+
+	vocabulary.iter().for_each(|x| {
+		display.entry(normalize_tag(x)).or_insert_with(|| clean_tag(x));
+	});
+
+	let mut spellings: HashMap<String, HashMap<String, usize>> = HashMap::new();
+	cards.iter().flat_map(|x| &x.tagy).for_each(|x| {
+		let tag = clean_tag(x);
+
+		tag_prefixes(&tag).into_iter().for_each(|prefix| {
+			*spellings
+				.entry(normalize_tag(prefix))
+				.or_default()
+				.entry(prefix.to_string())
+				.or_insert(0) += 1;
+		});
+	});
+
+	spellings.into_iter().for_each(|(k, v)| {
+		display.entry(k).or_insert_with(|| {
+			v.into_iter()
+				.max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+				.map(|x| x.0)
+				.unwrap_or_default()
+		});
+	});
+
+	// předkové tagů z `tags.toml` a slovníku, které nejsou v knize použité
+	declared.iter().map(|(name, _)| *name).chain(vocabulary).for_each(|x| {
+		let tag = clean_tag(x);
+
+		tag_prefixes(&tag).into_iter().for_each(|prefix| {
+			display.entry(normalize_tag(prefix)).or_insert_with(|| prefix.to_string());
+		});
+	});
+
+	let resolve = |tag: &str| {
+		let tag = normalize_tag(tag);
+		let tag = aliases.get(&tag).cloned().unwrap_or(tag);
+
+		tag_prefixes(&tag)
+			.into_iter()
+			.map(|prefix| {
+				display.get(prefix).map(|x| tag_leaf(x)).unwrap_or_else(|| tag_leaf(prefix))
+			})
+			.collect::<Vec<_>>()
+			.join("/")
+	};
+
+	cards.iter_mut().for_each(|x| {
+		let mut tagy: Vec<String> = vec![];
+
+		x.tagy.iter().filter(|t| !clean_tag(t).is_empty()).for_each(|t| {
+			let tag = resolve(t);
+
+			if !tagy.contains(&tag) {
+				tagy.push(tag);
+			}
+		});
+
+		x.tagy = tagy;
+	});
+}
+
+/// sesbírá tagy článků do hashmapy tag - články
+///
+/// článek s hierarchickým tagem je zařazen i pod všechny
+/// jeho předky, každý článek je pod tagem nejvýše jednou
+pub fn collect_tags(cards: &[ArticleCard]) -> HashMap<String, Vec<ArticleCard>> {
+	let mut tags: HashMap<String, Vec<ArticleCard>> = HashMap::new();
+
+	cards.iter().for_each(|x| {
+		x.tagy.iter().flat_map(|t| tag_prefixes(t)).for_each(|t| {
+			let articles = tags.entry(t.to_string()).or_default();
+
+			if !articles.iter().any(|y| y._resolved_path == x._resolved_path) {
+				articles.push(x.clone());
+			}
+		});
+	});
+
+	tags
+}
+
+/// sestaví strom hierarchických tagů z hashmapy tagů
+/// (viz [`collect_tags`]), uzly jsou seřazeny podle názvu
+pub fn tag_tree(tags: &HashMap<String, Vec<ArticleCard>>) -> Vec<TagNode> {
+	let mut nodes = tags
+		.iter()
+		.filter(|(k, _)| parent_tag(k).is_none())
+		.map(|(k, v)| tag_node(tags, k, v.len()))
+		.collect::<Vec<_>>();
+	nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+	nodes
+}
+
+/// vytvoří uzel stromu tagů i s potomky
+fn tag_node(tags: &HashMap<String, Vec<ArticleCard>>, path: &str, count: usize) -> TagNode {
+	let mut children = tags
+		.iter()
+		.filter(|(k, _)| parent_tag(k) == Some(path))
+		.map(|(k, v)| tag_node(tags, k, v.len()))
+		.collect::<Vec<_>>();
+	children.sort_by(|a, b| a.name.cmp(&b.name));
+
+	TagNode { name: tag_leaf(path).to_string(), path: path.to_string(), count, children }
+}
+
+/// Levenshteinova vzdálenost dvou řetězců (po znacích)
+pub fn levenshtein(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+	for (i, x) in a.chars().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+
+		for (j, y) in b.iter().enumerate() {
+			let above = row[j + 1];
+			row[j + 1] = (diagonal + (x != *y) as usize).min(above + 1).min(row[j] + 1);
+			diagonal = above;
+		}
+	}
+
+	row[b.len()]
+}
+
+/// najde nejvýše tři tagy ze slovníku nejpodobnější danému tagu
+pub fn suggest_tags<'a>(tag: &str, vocabulary: &'a [String]) -> Vec<&'a str> {
+	let tag = normalize_tag(tag);
+	let max_distance = (tag.chars().count() / 3).max(2);
+
+	let mut candidates = vocabulary
+		.iter()
+		.map(|x| (levenshtein(&tag, &normalize_tag(x)), x.as_str()))
+		.filter(|(d, _)| *d <= max_distance)
+		.collect::<Vec<_>>();
+	candidates.sort();
+	candidates.dedup_by(|a, b| a.1 == b.1);
+
+	candidates.into_iter().take(3).map(|(_, x)| x).collect()
+}
+
+/// zkontroluje, že všechny tagy článků jsou ve slovníku,
+/// pro každý tag mimo slovník vrátí chybu s návrhem podobných tagů.
+///
+/// Tagy musí být již sjednocené, viz [`canonicalize_tags`]
+pub fn check_vocabulary(cards: &[ArticleCard], vocabulary: &[String]) -> Vec<CatError> {
+	// předkové tagů ze slovníku jsou také povolené
+	let known = vocabulary
+		.iter()
+		.map(|x| normalize_tag(x))
+		.flat_map(|x| tag_prefixes(&x).into_iter().map(String::from).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+
+	cards
+		.iter()
+		.flat_map(|card| card.tagy.iter().map(move |tag| (card, tag)))
+		.filter(|(_, tag)| !known.contains(&normalize_tag(tag)))
+		.map(|(card, tag)| CatError::UnknownTag {
+			tag:        tag.clone(),
+			path:       card
+				._resolved_path
+				.as_ref()
+				.map(|x| x.display().to_string())
+				.unwrap_or_else(|| card.nazev.clone()),
+			suggestion: match suggest_tags(tag, vocabulary).as_slice() {
+				[] => "".into(),
+				s => format!(", did you mean '{}'?", s.join("', '")),
+			},
+		})
+		.collect()
+}
+
+/// úprava headeru jednoho článku
+#[derive(Debug, Clone)]
+pub struct TagEdit {
+	/// cesta k článku relativní ke kořeni knihy
+	pub path: PathBuf,
+	/// původní header
+	pub old:  String,
+	/// upravený header
+	pub new:  String,
+}
+
+impl TagEdit {
+	/// vytvoří jednoduchý diff headeru, vynechává
+	/// společný začátek a konec
+	pub fn diff(&self) -> String {
+		let old = self.old.lines().collect::<Vec<_>>();
+		let new = self.new.lines().collect::<Vec<_>>();
+
+		let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+		let suffix = old[prefix..]
+			.iter()
+			.rev()
+			.zip(new[prefix..].iter().rev())
+			.take_while(|(a, b)| a == b)
+			.count();
+
+		let old_count = old.len() - suffix - prefix;
+		let new_count = new.len() - suffix - prefix;
+		// prázdný rozsah začíná řádkem před změnou
+		let start = |count: usize| if count == 0 { prefix } else { prefix + 1 };
+
+		let mut res = vec![
+			format!("--- {}", self.path.display()),
+			format!("+++ {}", self.path.display()),
+			format!(
+				"@@ -{},{} +{},{} @@",
+				start(old_count),
+				old_count,
+				start(new_count),
+				new_count
+			),
+		];
+		res.extend(old[prefix..old.len() - suffix].iter().map(|l| format!("-{}", l)));
+		res.extend(new[prefix..new.len() - suffix].iter().map(|l| format!("+{}", l)));
+
+		res.join("\n") + "\n"
+	}
+}
+
+/// rozdělí soubor na header a zbytek (od řádku `+++`)
+fn split_header(content: &str) -> Option<(&str, &str)> {
+	let mut offset = 0;
+
+	for line in content.split_inclusive('\n') {
+		if line.trim_end_matches(&['\r', '\n'][..]) == "+++" {
+			return Some(content.split_at(offset));
+		}
+		offset += line.len();
+	}
+
+	None
+}
+
+/// zda je tag `tag` (po normalizaci) tagem `root` nebo jeho potomkem
+fn is_within(tag: &str, root: &str) -> bool {
+	let (tag, root) = (normalize_tag(tag), normalize_tag(root));
+	tag == root || tag.starts_with(&format!("{}/", root))
+}
+
+/// nový tvar tagu po nahrazení tagů `sources` tagem `target`
+///
+/// tagy se porovnávají v normalizovaném tvaru (viz [`normalize_tag`])
+/// a spolu s tagem se přejmenují i jeho potomci, tj. po přejmenování
+/// `fyzika` na `physics` je z `fyzika/mechanika` `physics/mechanika`.
+/// Vrací `None`, pokud tag nahrazen není
+fn renamed_tag(tag: &str, sources: &[&str], target: &str) -> Option<String> {
+	let tag = clean_tag(tag);
+	let parts = tag.split('/').collect::<Vec<_>>();
+
+	// při přesunu tagu pod jeho vlastního potomka (`a` na `a/b`)
+	// zůstávají tagy, které už pod cílovým tagem jsou
+	let into_child =
+		sources.iter().any(|s| is_within(target, s) && !is_within(s, target));
+	if into_child && is_within(&tag, target) {
+		return None;
+	}
+
+	sources.iter().find_map(|s| {
+		let depth = normalize_tag(s).split('/').count();
+
+		if parts.len() < depth || !is_within(&parts[..depth].join("/"), s) {
+			return None;
+		}
+
+		let rest = parts[depth..].iter().map(|x| format!("/{}", x)).collect::<String>();
+		Some(format!("{}{}", target, rest))
+	})
+}
+
+/// nahradí v poli `tagy` daného headeru tagy `sources` tagem `target`,
+/// viz [`renamed_tag`]
+///
+/// vrací `None`, pokud header nemá pole `tagy` nebo
+/// neobsahuje žádný z tagů `sources`
+pub fn retag_header(header: &str, sources: &[&str], target: &str) -> Option<String> {
+	let mut doc = header.parse::<Document>().ok()?;
+	let tagy = doc.get_mut("tagy")?.as_array_mut()?;
+
+	let renamed = tagy
+		.iter()
+		.map(|v| {
+			let tag = v.as_str()?;
+			renamed_tag(tag, sources, target).filter(|x| x != tag)
+		})
+		.collect::<Vec<_>>();
+
+	if renamed.iter().all(Option::is_none) {
+		return None;
+	}
+
+	// přejmenovaný tag, který už článek má, se odebere
+	let mut present = tagy
+		.iter()
+		.zip(&renamed)
+		.filter(|(_, r)| r.is_none())
+		.filter_map(|(v, _)| v.as_str().map(normalize_tag))
+		.collect::<Vec<_>>();
+	let mut duplicate = vec![];
+
+	for (i, tag) in renamed.iter().enumerate() {
+		if let Some(tag) = tag {
+			if present.contains(&normalize_tag(tag)) {
+				duplicate.push(i);
+			} else {
+				present.push(normalize_tag(tag));
+				tagy.replace(i, tag.as_str());
+			}
+		}
+	}
+
+	duplicate.into_iter().rev().for_each(|i| remove_at(tagy, i));
+
+	Some(doc.to_string())
+}
+
+/// odebere prvek pole, první prvek přebírá odsazení odebraného
+fn remove_at(array: &mut toml_edit::Array, index: usize) {
+	let removed = array.remove(index);
+
+	if index == 0 {
+		if let Some(first) = array.get_mut(0) {
+			*first.decor_mut() = removed.decor().clone();
+		}
+	}
+}
+
+/// nahradí ve všech článcích knihy tagy `sources` tagem `target`
+///
+/// pokud není nastaveno `dry_run`, upravené soubory
+/// se zapíší. Vrací seznam úprav.
+pub fn retag(
+	root: &Path,
+	sources: &[&str],
+	target: &str,
+	dry_run: bool,
+) -> Result<Vec<TagEdit>, CatError> {
+	let src = src_dir(root)?;
+	let mut edits = vec![];
+
+	let mut files = WalkDir::new(&src)
+		.into_iter()
+		.filter_map(|e| e.ok())
+		.filter(|e| e.file_type().is_file())
+		.filter(|e| e.path().extension().map(|x| x == "md").unwrap_or(false))
+		.map(|e| e.path().to_path_buf())
+		.collect::<Vec<_>>();
+	files.sort();
+
+	for path in files {
+		let content = fs::read_to_string(&path).map_err(|e| CatError::ReadFailed {
+			path:  path.display().to_string(),
+			error: e.to_string(),
+		})?;
+
+		let (header, rest) = match split_header(&content) {
+			Some(hr) => hr,
+			None => continue,
+		};
+
+		let new = match retag_header(header, sources, target) {
+			Some(new) if new != header => new,
+			_ => continue,
+		};
+
+		if !dry_run {
+			write_if_changed(&path, &format!("{}{}", new, rest))?;
+		}
+
+		edits.push(TagEdit {
+			path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+			old:  header.to_string(),
+			new,
+		});
+	}
+
+	Ok(edits)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn edit(old: &str, new: &str) -> TagEdit {
+		TagEdit { path: "src/a.md".into(), old: old.into(), new: new.into() }
+	}
+
+	#[test]
+	fn diff_hunk_header_has_line_counts() {
+		assert_eq!(
+			edit("a\nb\nc\n", "a\nB\nB2\nc\n").diff(),
+			"--- src/a.md\n+++ src/a.md\n@@ -2,1 +2,2 @@\n-b\n+B\n+B2\n"
+		);
+		// prázdný rozsah začíná řádkem před změnou
+		assert_eq!(
+			edit("a\nc\n", "a\nb\nc\n").diff(),
+			"--- src/a.md\n+++ src/a.md\n@@ -1,0 +2,1 @@\n+b\n"
+		);
+		assert_eq!(
+			edit("a\nb\n", "b\n").diff(),
+			"--- src/a.md\n+++ src/a.md\n@@ -1,1 +0,0 @@\n-a\n"
+		);
+	}
+
+	fn retag_tags(tagy: &str, sources: &[&str], target: &str) -> Option<String> {
+		let header = format!("nazev = \"Článek\"\ntagy = {}\n", tagy);
+		let new = retag_header(&header, sources, target)?;
+
+		Some(new.lines().nth(1).unwrap().trim_start_matches("tagy = ").to_string())
+	}
+
+	#[test]
+	fn retag_compares_normalized_tags() {
+		assert_eq!(
+			retag_tags("[\"Python\", \"rust\"]", &["python"], "programování/python"),
+			Some("[\"programování/python\", \"rust\"]".into())
+		);
+		// NFD zápis `é`
+		assert_eq!(
+			retag_tags("[\"cafe\u{301}\"]", &["café"], "kavárna"),
+			Some("[\"kavárna\"]".into())
+		);
+		assert_eq!(retag_tags("[\"python3\"]", &["python"], "Python"), None);
+	}
+
+	#[test]
+	fn retag_renames_child_tags() {
+		assert_eq!(
+			retag_tags(
+				"[\"fyzika\", \"Fyzika/mechanika\", \"fyzikální\"]",
+				&["fyzika"],
+				"physics"
+			),
+			Some("[\"physics\", \"physics/mechanika\", \"fyzikální\"]".into())
+		);
+		// `a/b` už je pod cílovým tagem a nepřesouvá se na `a/b/b`
+		assert_eq!(
+			retag_tags("[\"a\", \"a/b\", \"a/c\"]", &["a"], "a/b"),
+			Some("[\"a/b\", \"a/b/c\"]".into())
+		);
+	}
+
+	#[test]
+	fn retag_merges_into_existing_tag() {
+		assert_eq!(
+			retag_tags("[\"py\", \"rust\", \"Python\"]", &["py", "python3"], "Python"),
+			Some("[\"rust\", \"Python\"]".into())
+		);
+		assert_eq!(
+			retag_tags("[\"python\", \"Python\"]", &["python"], "Python"),
+			Some("[\"Python\"]".into())
+		);
+	}
+}
@@ -0,0 +1,31 @@
+//! modul obsahující pomocné funkce pro práci se soubory
+//!
+//! `cat-prep` zapisuje soubory jak při sestavování knihy
+//! (feedy, export), tak v příkazech, které knihu upravují
+//! (`new`, `summary sync`, `tags`).
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::CatError;
+
+/// zapíše soubor, pokud se jeho obsah liší od obsahu na disku
+///
+/// chybějící nadřazené složky jsou vytvořeny. Nezměněné soubory
+/// se nepřepisují, aby `mdbook serve` nesestavoval knihu stále dokola
+pub fn write_if_changed(path: &Path, content: &str) -> Result<(), CatError> {
+	if fs::read_to_string(path).map(|x| x == content).unwrap_or(false) {
+		return Ok(());
+	}
+
+	let err = |e: std::io::Error| CatError::WriteFailed {
+		path:  path.display().to_string(),
+		error: e.to_string(),
+	};
+
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent).map_err(err)?;
+	}
+
+	fs::write(path, content).map_err(err)
+}